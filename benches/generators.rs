@@ -0,0 +1,36 @@
+//! Times every maze generator on large grids.
+//!
+//! Run with `cargo bench --bench generators`.
+
+use maze3d::generator::GENERATORS;
+use maze3d::map::Size;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const RUNS: u64 = 10;
+
+fn main() {
+    for (width, height) in [(201, 201), (999, 999)] {
+        let size = Size::new(width, height).unwrap();
+        println!("{width}x{height}");
+        for generator in GENERATORS {
+            let mut times: Vec<Duration> = (0..RUNS)
+                .map(|seed| {
+                    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                    let start = Instant::now();
+                    black_box(generator.generate(&mut rng, size));
+                    start.elapsed()
+                })
+                .collect();
+            times.sort();
+            println!(
+                "  {:<16} median {:>10.3?}  min {:>10.3?}",
+                generator.name(),
+                times[times.len() / 2],
+                times[0]
+            );
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Prints how hard the mazes of every generator are on average, see
+//! `MazeStats`.
+//!
+//! Usage: `cargo run --release --example compare_generators -- [WIDTHxHEIGHT] [RUNS]`
+
+use maze3d::generator::GENERATORS;
+use maze3d::map::{MazeStats, Size};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let size = match args.next() {
+        Some(arg) => arg.parse().unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }),
+        None => Size::new(41, 41).unwrap(),
+    };
+    let runs: u64 = match args.next().map(|arg| arg.parse()) {
+        Some(Ok(runs @ 1..)) => runs,
+        Some(_) => {
+            eprintln!("Usage: compare_generators [WIDTHxHEIGHT] [RUNS], with RUNS above zero");
+            std::process::exit(2);
+        }
+        None => 20,
+    };
+
+    println!(
+        "{:<16} {:>8} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "generator", "solution", "dead ends", "junctions", "corridor", "decisions", "reachable"
+    );
+    for generator in GENERATORS {
+        let stats: Vec<_> = (0..runs)
+            .map(|seed| {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                generator.generate(&mut rng, size).stats()
+            })
+            .collect();
+        let average =
+            |f: &dyn Fn(&MazeStats) -> f64| stats.iter().map(f).sum::<f64>() / stats.len() as f64;
+        println!(
+            "{:<16} {:>8.1} {:>9.1} {:>9.1} {:>9.2} {:>9.1} {:>8.0}%",
+            generator.name(),
+            average(&|s| s.solution_length.unwrap_or(0) as f64),
+            average(&|s| s.dead_ends as f64),
+            average(&|s| s.junctions as f64),
+            average(&|s| s.average_corridor_length),
+            average(&|s| s.decision_points as f64),
+            average(&|s| s.reachable_ratio) * 100.0
+        );
+    }
+}
@@ -0,0 +1,48 @@
+//! Writes a maze of any size to stdout as text, one line per row of tiles,
+//! without holding the whole maze in memory.
+//!
+//! Usage: `cargo run --release --example export_maze -- WIDTHxHEIGHT [SEED]`,
+//! with a seed as shown in the game.
+
+use maze3d::game::{self, Seed, SettingsError};
+use maze3d::generator::EllerRows;
+use maze3d::map::{Size, Tile};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::io::{self, BufWriter, Write};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let size = match args.next().as_deref().map(Size::parse_unbounded) {
+        Some(Ok(size)) => size,
+        Some(Err(e)) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+        None => {
+            eprintln!("Usage: export_maze WIDTHxHEIGHT [SEED]");
+            std::process::exit(2);
+        }
+    };
+    let seed = match args.next() {
+        Some(arg) => game::parse_seed(&arg).unwrap_or_else(|| {
+            eprintln!("{}", SettingsError::InvalidSeed(arg));
+            std::process::exit(2);
+        }),
+        None => Seed::default(),
+    };
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut line = String::new();
+    for row in EllerRows::new(ChaCha8Rng::from_seed(seed), size) {
+        line.clear();
+        line.extend(row.iter().map(|tile| match tile {
+            Tile::Wall => '#',
+            _ => ' ',
+        }));
+        if writeln!(out, "{line}").is_err() {
+            return;
+        }
+    }
+    _ = out.flush();
+}
@@ -0,0 +1,89 @@
+//! Plays the maze in a terminal, e.g. over SSH.
+//!
+//! Usage: `maze3d-tui [OPTIONS]`, with the options read by
+//! [`Settings::from_args`].
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    execute, queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+use maze3d::game::{GameState, Settings, SettingsError};
+use maze3d::text;
+use std::io::{self, Write};
+
+#[derive(thiserror::Error, Debug)]
+enum ApplicationError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Settings(#[from] SettingsError),
+}
+
+/// Puts the terminal into raw mode on the alternate screen, and restores it
+/// when dropped, even on errors.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let guard = TerminalGuard;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn draw(out: &mut impl Write, state: &GameState) -> io::Result<()> {
+    queue!(out, terminal::Clear(ClearType::All))?;
+    for (y, line) in text::draw(state).iter().enumerate() {
+        queue!(out, cursor::MoveTo(0, y as u16), Print(line))?;
+    }
+    out.flush()
+}
+
+fn run() -> Result<(), ApplicationError> {
+    let settings = Settings::from_args(std::env::args().skip(1))?;
+
+    let _guard = TerminalGuard::new()?;
+    let mut out = io::stdout();
+    let mut state = GameState::first_game(settings);
+
+    loop {
+        draw(&mut out, &state)?;
+
+        let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event::read()?
+        else {
+            continue;
+        };
+        match code {
+            KeyCode::Left => state.turn_left(),
+            KeyCode::Up => state.move_forward(),
+            KeyCode::Right => state.turn_right(),
+            KeyCode::Down => state.turn_back(),
+            KeyCode::Char('m' | 'M') => state.toggle_mini_map(),
+            KeyCode::Enter => state = state.next_game(),
+            KeyCode::Esc => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("Error: {error}");
+        std::process::exit(1);
+    }
+}
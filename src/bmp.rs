@@ -0,0 +1,247 @@
+//! Images, read from and written to uncompressed Windows bitmaps, for the
+//! renderers to draw and for masks to be made from.
+
+use crate::render::{color_rgb, Color};
+
+#[derive(thiserror::Error, Debug)]
+pub enum BmpError {
+    #[error("Not a BMP file")]
+    NotBmp,
+    #[error("Unsupported BMP format ({0})")]
+    Unsupported(String),
+    #[error("The BMP data is truncated")]
+    Truncated,
+}
+
+/// An RGBA image, 8 bits per channel, stored row by row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, color: Color) -> Self {
+        let pixels = [color.r, color.g, color.b, 255].repeat((width * height) as usize);
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn from_bmp(data: &[u8]) -> Result<Self, BmpError> {
+        decode(data)
+    }
+
+    pub fn to_bmp(&self) -> Vec<u8> {
+        encode(self)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn as_rgba(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> Color {
+        let i = ((x + y * self.width) * 4) as usize;
+        color_rgb(self.pixels[i], self.pixels[i + 1], self.pixels[i + 2])
+    }
+
+    /// Sets a pixel, ignoring coordinates outside of the image.
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if x < 0 || x as u32 >= self.width || y < 0 || y as u32 >= self.height {
+            return;
+        }
+        let i = ((x as u32 + y as u32 * self.width) * 4) as usize;
+        self.pixels[i..i + 4].copy_from_slice(&[color.r, color.g, color.b, 255]);
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, BmpError> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or(BmpError::Truncated)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, BmpError> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(BmpError::Truncated)
+}
+
+/// Decodes a BMP with a `BITMAPINFOHEADER` (or a later version of it),
+/// 1/4/8 bits per pixel with a palette or 24/32 bits per pixel, uncompressed.
+fn decode(data: &[u8]) -> Result<Image, BmpError> {
+    if data.get(0..2) != Some(b"BM") {
+        return Err(BmpError::NotBmp);
+    }
+    let pixel_offset = read_u32(data, 10)? as usize;
+    let header_size = read_u32(data, 14)? as usize;
+    if header_size < 40 {
+        return Err(BmpError::Unsupported(format!("header size {header_size}")));
+    }
+    let width = read_u32(data, 18)? as i32;
+    let height = read_u32(data, 22)? as i32;
+    let bit_count = read_u16(data, 28)?;
+    let compression = read_u32(data, 30)?;
+    let palette_size = read_u32(data, 46)?;
+
+    // BI_RGB, or BI_BITFIELDS which is the default layout for the 32 bits we read
+    if compression != 0 && !(compression == 3 && bit_count == 32) {
+        return Err(BmpError::Unsupported(format!("compression {compression}")));
+    }
+    if width <= 0 || height == 0 {
+        return Err(BmpError::Unsupported(format!("size {width}x{height}")));
+    }
+
+    let palette = match bit_count {
+        1 | 4 | 8 => {
+            let count = if palette_size == 0 {
+                1 << bit_count
+            } else {
+                palette_size as usize
+            };
+            let start = 14 + header_size;
+            (0..count)
+                .map(|i| {
+                    let entry = data
+                        .get(start + i * 4..start + i * 4 + 3)
+                        .ok_or(BmpError::Truncated)?;
+                    Ok(color_rgb(entry[2], entry[1], entry[0]))
+                })
+                .collect::<Result<Vec<Color>, BmpError>>()?
+        }
+        24 | 32 => Vec::new(),
+        _ => return Err(BmpError::Unsupported(format!("{bit_count} bits per pixel"))),
+    };
+
+    let width = width as u32;
+    let is_top_down = height < 0;
+    let height = height.unsigned_abs();
+    // The header is not trusted with the size of the image until the data holds its pixels
+    let too_large = || BmpError::Unsupported(format!("size {width}x{height}"));
+    let stride = (width as usize)
+        .checked_mul(bit_count as usize)
+        .ok_or_else(too_large)?
+        .div_ceil(32)
+        * 4;
+    let end = stride
+        .checked_mul(height as usize)
+        .and_then(|size| size.checked_add(pixel_offset))
+        .ok_or_else(too_large)?;
+    if data.len() < end {
+        return Err(BmpError::Truncated);
+    }
+
+    let mut image = Image::new(width, height, color_rgb(0, 0, 0));
+    for row in 0..height {
+        let start = pixel_offset + row as usize * stride;
+        let line = data.get(start..start + stride).ok_or(BmpError::Truncated)?;
+        let y = if is_top_down { row } else { height - 1 - row };
+        for x in 0..width as usize {
+            let color = match bit_count {
+                24 | 32 => {
+                    let i = x * bit_count as usize / 8;
+                    color_rgb(line[i + 2], line[i + 1], line[i])
+                }
+                _ => {
+                    let bit = x * bit_count as usize;
+                    let byte = line[bit / 8];
+                    let shift = 8 - bit_count as usize - bit % 8;
+                    let index = (byte >> shift) & ((1 << bit_count) - 1) as u8;
+                    *palette
+                        .get(index as usize)
+                        .ok_or_else(|| BmpError::Unsupported(format!("palette index {index}")))?
+                }
+            };
+            image.set_pixel(x as i32, y as i32, color);
+        }
+    }
+
+    Ok(image)
+}
+
+/// Encodes `image` as a 24 bits per pixel, bottom-up BMP.
+fn encode(image: &Image) -> Vec<u8> {
+    let (width, height) = (image.width(), image.height());
+    let stride = (width as usize * 3).div_ceil(4) * 4;
+    let pixel_offset = 14 + 40;
+    let file_size = pixel_offset + stride * height as usize;
+
+    let mut data = Vec::with_capacity(file_size);
+    data.extend_from_slice(b"BM");
+    data.extend_from_slice(&(file_size as u32).to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&(pixel_offset as u32).to_le_bytes());
+
+    data.extend_from_slice(&40u32.to_le_bytes());
+    data.extend_from_slice(&(width as i32).to_le_bytes());
+    data.extend_from_slice(&(height as i32).to_le_bytes());
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&24u16.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&((stride * height as usize) as u32).to_le_bytes());
+    // 72 DPI
+    data.extend_from_slice(&2835u32.to_le_bytes());
+    data.extend_from_slice(&2835u32.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+
+    for y in (0..height).rev() {
+        let start = data.len();
+        for x in 0..width {
+            let color = image.get_pixel(x, y);
+            data.extend_from_slice(&[color.b, color.g, color.r]);
+        }
+        data.resize(start + stride, 0);
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut image = Image::new(3, 2, color_rgb(1, 2, 3));
+        image.set_pixel(0, 0, color_rgb(255, 0, 0));
+        image.set_pixel(2, 1, color_rgb(0, 0, 255));
+        assert_eq!(decode(&encode(&image)).unwrap(), image);
+    }
+
+    #[test]
+    fn decodes_wall_asset() {
+        let data = std::fs::read("assets/wall.bmp").unwrap();
+        let image = decode(&data).unwrap();
+        assert_eq!((image.width(), image.height()), (1024, 256));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(matches!(decode(b"PK\x03\x04"), Err(BmpError::NotBmp)));
+        assert!(matches!(decode(b"BM"), Err(BmpError::Truncated)));
+    }
+
+    #[test]
+    fn checks_the_size_against_the_data() {
+        let mut data = encode(&Image::new(3, 2, color_rgb(1, 2, 3)));
+        data[18..22].copy_from_slice(&i32::MAX.to_le_bytes());
+        data[22..26].copy_from_slice(&i32::MIN.to_le_bytes());
+        assert!(matches!(decode(&data), Err(BmpError::Truncated)));
+
+        let mut data = encode(&Image::new(3, 2, color_rgb(1, 2, 3)));
+        data.truncate(data.len() - 1);
+        assert!(matches!(decode(&data), Err(BmpError::Truncated)));
+    }
+}
@@ -0,0 +1,178 @@
+use maze3d::game::{GameState, Settings, SettingsError};
+use maze3d::render::software::{self, Framebuffer};
+use maze3d::render::Canvas;
+use maze3d::view::{self, GameView};
+use std::num::NonZeroU32;
+use std::rc::Rc;
+use winit::{
+    application::ApplicationHandler,
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent, WindowEvent},
+    event_loop::{ActiveEventLoop, EventLoop},
+    keyboard::{KeyCode, PhysicalKey},
+    window::{Window, WindowId},
+};
+
+#[derive(thiserror::Error, Debug)]
+enum ApplicationError {
+    #[error(transparent)]
+    EventLoop(#[from] winit::error::EventLoopError),
+    #[error(transparent)]
+    Os(#[from] winit::error::OsError),
+    #[error(transparent)]
+    Surface(#[from] softbuffer::SoftBufferError),
+    #[error(transparent)]
+    Game(#[from] view::Error<software::Error>),
+    #[error(transparent)]
+    Settings(#[from] SettingsError),
+}
+
+struct Presenter {
+    window: Rc<Window>,
+    surface: softbuffer::Surface<Rc<Window>, Rc<Window>>,
+}
+
+struct App {
+    state: GameState,
+    view: GameView<Framebuffer>,
+    frame: Framebuffer,
+    presenter: Option<Presenter>,
+    error: Option<ApplicationError>,
+}
+
+impl App {
+    fn create_presenter(
+        &self,
+        event_loop: &ActiveEventLoop,
+    ) -> Result<Presenter, ApplicationError> {
+        let (width, height) = self.frame.get_size();
+        let attributes = Window::default_attributes()
+            .with_title("3d Maze")
+            .with_inner_size(PhysicalSize::new(width, height))
+            .with_resizable(false);
+        let window = Rc::new(event_loop.create_window(attributes)?);
+
+        let context = softbuffer::Context::new(window.clone())?;
+        let surface = softbuffer::Surface::new(&context, window.clone())?;
+
+        Ok(Presenter { window, surface })
+    }
+
+    fn on_key(&mut self, event_loop: &ActiveEventLoop, key: KeyCode) {
+        match key {
+            KeyCode::ArrowLeft => self.state.turn_left(),
+            KeyCode::ArrowUp => self.state.move_forward(),
+            KeyCode::ArrowRight => self.state.turn_right(),
+            KeyCode::ArrowDown => self.state.turn_back(),
+            KeyCode::KeyM => self.state.toggle_mini_map(),
+            KeyCode::Enter => {
+                self.state = self.state.next_game();
+                self.view.invalidate_mini_map();
+            }
+            KeyCode::Escape => {
+                event_loop.exit();
+                return;
+            }
+            _ => return,
+        }
+        if let Some(presenter) = &self.presenter {
+            presenter.window.request_redraw();
+        }
+    }
+
+    fn redraw(&mut self) -> Result<(), ApplicationError> {
+        let Some(presenter) = &mut self.presenter else {
+            return Ok(());
+        };
+
+        self.view.draw(&self.state, &mut self.frame)?;
+
+        let size = presenter.window.inner_size();
+        let (Some(width), Some(height)) =
+            (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
+        else {
+            return Ok(());
+        };
+        presenter.surface.resize(width, height)?;
+
+        let image = self.frame.image();
+        let mut buffer = presenter.surface.buffer_mut()?;
+        // The window may be larger than requested, e.g. with a tiling window manager
+        buffer.fill(0x00FF_FFFF);
+        for y in 0..size.height.min(image.height()) {
+            for x in 0..size.width.min(image.width()) {
+                let color = image.get_pixel(x, y);
+                buffer[(x + y * size.width) as usize] =
+                    (color.r as u32) << 16 | (color.g as u32) << 8 | color.b as u32;
+            }
+        }
+        buffer.present()?;
+
+        Ok(())
+    }
+
+    fn fail(&mut self, event_loop: &ActiveEventLoop, error: ApplicationError) {
+        self.error = Some(error);
+        event_loop.exit();
+    }
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.presenter.is_some() {
+            return;
+        }
+        match self.create_presenter(event_loop) {
+            Ok(presenter) => self.presenter = Some(presenter),
+            Err(e) => self.fail(event_loop, e),
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::RedrawRequested => {
+                if let Err(e) = self.redraw() {
+                    self.fail(event_loop, e);
+                }
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(key),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => self.on_key(event_loop, key),
+            _ => {}
+        }
+    }
+}
+
+pub fn main() {
+    if let Err(error) = run() {
+        eprintln!("Error: {error}");
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), ApplicationError> {
+    let settings = Settings::from_args(std::env::args().skip(1))?;
+
+    let event_loop = EventLoop::new()?;
+
+    let mut app = App {
+        state: GameState::first_game(settings),
+        view: GameView::new(),
+        frame: Framebuffer::new(view::WIDTH, view::HEIGHT),
+        presenter: None,
+        error: None,
+    };
+    event_loop.run_app(&mut app)?;
+
+    if let Some(e) = app.error {
+        return Err(e);
+    }
+    Ok(())
+}
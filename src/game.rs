@@ -1,444 +1,808 @@
-use crate::map::{Map, Tile};
-use crate::player::Player;
-use crate::render::{
-    color_rgb, point, rect, rect_wh, Bitmap, Brush, Context, Font, Geometry, ImageLoader,
-    RenderTarget,
-};
-use rand::{Rng, SeedableRng};
-use std::fmt::Write;
-
-#[derive(thiserror::Error, Debug)]
-pub enum Error {
-    #[error(transparent)]
-    GeneralRenderingError(#[from] crate::render::Error),
-    #[error("An error at the end of drawing")]
-    EndDrawError,
-}
-
-struct RenderingData {
-    map_surface: RenderTarget,
-    wall_surface: Bitmap,
-    mini_map_surface: RenderTarget,
-
-    black_brush: Brush,
-    white_brush: Brush,
-    start_brush: Brush,
-    goal_brush: Brush,
-}
-
-pub struct Game {
-    rng_seed: <rand_chacha::ChaCha8Rng as rand::SeedableRng>::Seed,
-    rng: rand_chacha::ChaCha8Rng,
-
-    map: Map,
-    player: Player,
-
-    shows_mini_map: bool,
-    is_goal: bool,
-    score: u32,
-
-    mini_map_view_count: u32,
-    key_press_count: u32,
-
-    drew_mini_map: bool,
-    player_geometry: Geometry,
-    seed_font: Font,
-    message_font: Font,
-    rendering_data: Option<RenderingData>,
-    image_loader: ImageLoader,
-}
-
-impl Game {
-    pub fn new(render_context: &Context) -> Result<Self, Error> {
-        let mut rng_seed = <rand_chacha::ChaCha8Rng as rand::SeedableRng>::Seed::default();
-        rand::thread_rng().fill(&mut rng_seed);
-        let mut rng = rand_chacha::ChaCha8Rng::from_seed(rng_seed);
-
-        let map = Map::new(&mut rng, 21, 21);
-        let player = Player {
-            x: map.start_x,
-            y: map.start_y,
-            direction: crate::player::Direction::South,
-        };
-
-        let player_geometry = render_context.create_geometry(|p| {
-            p.begin_figure(&point(0, (256 / map.height / 2) as i32));
-            p.add_line(&point((256 / map.width) as i32, 0));
-            p.add_line(&point((256 / map.width) as i32, (256 / map.height) as i32));
-            p.end_figure();
-        })?;
-
-        let seed_font = render_context.create_font("MS Gothic", 12)?;
-        let message_font = render_context.create_font("MS UI Gothic", 20)?;
-
-        Ok(Game {
-            rng_seed,
-            rng,
-
-            map,
-            player,
-
-            shows_mini_map: false,
-            is_goal: false,
-            score: 0,
-
-            mini_map_view_count: 0,
-            key_press_count: 0,
-
-            drew_mini_map: false,
-            player_geometry,
-            seed_font,
-            message_font,
-            rendering_data: None,
-
-            image_loader: ImageLoader::new()?,
-        })
-    }
-
-    pub fn turn_left(&mut self) {
-        if self.is_goal {
-            return;
-        }
-        use crate::player::Direction;
-        self.player.direction = match self.player.direction {
-            Direction::West => Direction::South,
-            Direction::North => Direction::West,
-            Direction::East => Direction::North,
-            Direction::South => Direction::East,
-        };
-        self.key_press_count += 1;
-    }
-
-    pub fn turn_right(&mut self) {
-        if self.is_goal {
-            return;
-        }
-        use crate::player::Direction;
-        self.player.direction = match self.player.direction {
-            Direction::West => Direction::North,
-            Direction::North => Direction::East,
-            Direction::East => Direction::South,
-            Direction::South => Direction::West,
-        };
-        self.key_press_count += 1;
-    }
-
-    pub fn turn_back(&mut self) {
-        if self.is_goal {
-            return;
-        }
-        use crate::player::Direction;
-        self.player.direction = match self.player.direction {
-            Direction::West => Direction::East,
-            Direction::North => Direction::South,
-            Direction::East => Direction::West,
-            Direction::South => Direction::North,
-        };
-        self.key_press_count += 1;
-    }
-
-    pub fn move_forward(&mut self) {
-        if self.is_goal {
-            return;
-        }
-        use crate::player::Direction;
-        let point_diff = match self.player.direction {
-            Direction::West => (-1, 0),
-            Direction::North => (0, -1),
-            Direction::East => (1, 0),
-            Direction::South => (0, 1),
-        };
-        let Some(new_x) = self.player.x.checked_add_signed(point_diff.0) else {
-            return;
-        };
-        if new_x >= self.map.width {
-            return;
-        }
-        let Some(new_y) = self.player.y.checked_add_signed(point_diff.1) else {
-            return;
-        };
-        if new_y >= self.map.height {
-            return;
-        }
-        if self
-            .map
-            .get(new_x as i32, new_y as i32)
-            .unwrap_or(Tile::Wall)
-            == Tile::Wall
-        {
-            return;
-        }
-
-        self.player.x = new_x;
-        self.player.y = new_y;
-        self.key_press_count += 1;
-
-        if new_x == self.map.goal_x && new_y == self.map.goal_y {
-            self.is_goal = true;
-            self.score = 5000 / self.key_press_count
-                + if self.mini_map_view_count == 0 {
-                    50
-                } else {
-                    10 / self.mini_map_view_count
-                }
-                + self.rng.gen_range(0..30);
-        }
-    }
-
-    pub fn toggle_mini_map(&mut self) {
-        if self.is_goal {
-            return;
-        }
-        self.shows_mini_map = !self.shows_mini_map;
-        self.mini_map_view_count += 1;
-    }
-
-    pub fn new_game(&mut self, render_context: &Context) -> Result<Game, Error> {
-        let mut new = Game::new(render_context)?;
-        new.rendering_data = self.rendering_data.take();
-        Ok(new)
-    }
-
-    pub fn draw(&mut self, rt: &RenderTarget) -> Result<(), Error> {
-        let r = self
-            .rendering_data
-            .take()
-            .map_or_else(|| self.create_rendering_data(rt), Ok)?;
-
-        rt.begin();
-
-        rt.clear(color_rgb(255, 255, 255));
-
-        rt.draw_text(
-            &format!(
-                "seed: 0x{}",
-                self.rng_seed.iter().fold(String::new(), |mut r, v| {
-                    _ = write!(r, "{v:02X}");
-                    r
-                })
-            ),
-            0,
-            0,
-            &self.seed_font,
-            &r.black_brush,
-        );
-
-        rt.draw_rect(&rect_wh(48 - 1, 48 - 1, 256 + 2, 256 + 2), &r.black_brush);
-        self.draw_wall(&r);
-        rt.copy_from(
-            &rect_wh(48, 48, 256, 256),
-            &r.map_surface.get_bitmap()?,
-            0,
-            0,
-        );
-
-        let mini_map_x = 48 * 2 + 256;
-        let mini_map_y = 48;
-        rt.draw_rect(
-            &rect_wh(mini_map_x - 1, mini_map_y - 1, 256 + 2, 256 + 2),
-            &r.black_brush,
-        );
-        if self.shows_mini_map {
-            if !self.drew_mini_map {
-                self.draw_mini_map(&r);
-            }
-            rt.copy_from(
-                &rect_wh(mini_map_x, mini_map_y, 256, 256),
-                &r.mini_map_surface.get_bitmap()?,
-                0,
-                0,
-            );
-            let angle = match self.player.direction {
-                crate::player::Direction::West => 0.0,
-                crate::player::Direction::North => 90.0,
-                crate::player::Direction::East => 180.0,
-                crate::player::Direction::South => 270.0,
-            };
-            rt.draw_polygon(
-                &self.player_geometry,
-                mini_map_x + (self.player.x * 256 / self.map.width) as i32,
-                mini_map_y + (self.player.y * 256 / self.map.height) as i32,
-                &r.black_brush,
-                &r.white_brush,
-                angle,
-            );
-        }
-
-        if self.is_goal {
-            let text = format!(
-                "ゴール！　スコア：{}点　リスタート：Enterキー　終了：ESCキー",
-                self.score
-            );
-            rt.draw_text(&text, 20, 48 + 256 + 12, &self.message_font, &r.black_brush);
-        } else {
-            rt.draw_text(
-                "移動：矢印キー マップ：Mキー 終了：ESCキー",
-                20,
-                48 + 256 + 12,
-                &self.message_font,
-                &r.black_brush,
-            );
-        }
-
-        if rt.end() {
-            self.rendering_data = Some(r);
-        } else {
-            self.rendering_data = None;
-            return Err(Error::EndDrawError);
-        }
-
-        Ok(())
-    }
-
-    fn create_rendering_data(&mut self, rt: &RenderTarget) -> Result<RenderingData, Error> {
-        let map_surface = rt.create_new_render_target(256, 256)?;
-        let wall_surface = self.image_loader.load_bitmap("assets\\wall.bmp", rt)?;
-        let mini_map_surface = rt.create_new_render_target(256, 256)?;
-        let black_brush = rt.create_solid_brush(color_rgb(0, 0, 0))?;
-        let white_brush = rt.create_solid_brush(color_rgb(255, 255, 255))?;
-        let start_brush = rt.create_solid_brush(color_rgb(0, 255, 255))?;
-        let goal_brush = rt.create_solid_brush(color_rgb(255, 0, 0))?;
-
-        self.drew_mini_map = false;
-
-        Ok(RenderingData {
-            map_surface,
-            wall_surface,
-            mini_map_surface,
-            black_brush,
-            white_brush,
-            start_brush,
-            goal_brush,
-        })
-    }
-
-    fn draw_wall(&self, r: &RenderingData) {
-        r.map_surface.begin();
-        r.map_surface.clear(color_rgb(0, 0, 0));
-
-        r.map_surface
-            .copy_from(&rect_wh(0, 0, 256, 256), &r.wall_surface, 0, 0);
-
-        let sight = self.player.sight(&self.map);
-
-        // Forward 3
-        if sight.get(3, -3) == Tile::Wall {
-            r.map_surface.copy_from(
-                &rect_wh(0, 108, 40 - 12, 40),
-                &r.wall_surface,
-                876 + 12,
-                108,
-            )
-        }
-        if sight.get(3, -2) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(28, 108, 40, 40), &r.wall_surface, 876, 108)
-        }
-        if sight.get(3, -1) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(68, 108, 40, 40), &r.wall_surface, 876, 108)
-        }
-        if sight.get(3, 1) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(148, 108, 40, 40), &r.wall_surface, 876, 108)
-        }
-        if sight.get(3, 2) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(188, 108, 40, 40), &r.wall_surface, 876, 108)
-        }
-        if sight.get(3, 3) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(228, 108, 256 - 228, 40), &r.wall_surface, 876, 108)
-        }
-        if sight.get(3, 0) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(108, 108, 40, 40), &r.wall_surface, 876, 108)
-        }
-
-        // Forward 2
-        if sight.get(2, -2) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(0, 72, 21, 112), &r.wall_surface, 768, 72)
-        }
-        if sight.get(2, -1) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(21, 72, 89, 112), &r.wall_surface, 788, 72)
-        }
-        if sight.get(2, 1) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(147, 72, 89, 112), &r.wall_surface, 915, 72)
-        }
-        if sight.get(2, 2) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(235, 72, 21, 112), &r.wall_surface, 1003, 72)
-        }
-        if sight.get(2, 0) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(72, 72, 112, 112), &r.wall_surface, 584, 72)
-        }
-
-        // Forward 1
-        if sight.get(1, -1) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(0, 20, 73, 216), &r.wall_surface, 512, 20)
-        }
-        if sight.get(1, 1) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(183, 20, 73, 216), &r.wall_surface, 695, 20)
-        }
-        if sight.get(1, 0) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(20, 20, 216, 216), &r.wall_surface, 276, 20)
-        }
-
-        // Forward 0
-        if sight.get(0, -1) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(0, 0, 20, 256), &r.wall_surface, 256, 0)
-        }
-        if sight.get(0, 1) == Tile::Wall {
-            r.map_surface
-                .copy_from(&rect_wh(236, 0, 20, 256), &r.wall_surface, 492, 0)
-        }
-
-        r.map_surface.end();
-    }
-
-    fn draw_mini_map(&mut self, r: &RenderingData) {
-        r.mini_map_surface.begin();
-        r.mini_map_surface.clear(color_rgb(255, 255, 255));
-        let surface_size = r.mini_map_surface.get_size();
-
-        let map_size = (self.map.width, self.map.height);
-        let rect_at = |x: u32, y: u32| {
-            rect(
-                (x * surface_size.0 / map_size.0) as i32,
-                (y * surface_size.1 / map_size.1) as i32,
-                ((x + 1) * surface_size.0 / map_size.0) as i32,
-                ((y + 1) * surface_size.1 / map_size.1) as i32,
-            )
-        };
-        for y in 0..self.map.height {
-            for x in 0..self.map.width {
-                if let Some(Tile::Floor) = self.map.get(x as i32, y as i32) {
-                    continue;
-                }
-
-                r.mini_map_surface.fill_rect(&rect_at(x, y), &r.black_brush);
-            }
-        }
-
-        r.mini_map_surface
-            .fill_rect(&rect_at(self.map.start_x, self.map.start_y), &r.start_brush);
-        r.mini_map_surface
-            .fill_rect(&rect_at(self.map.goal_x, self.map.goal_y), &r.goal_brush);
-
-        r.mini_map_surface.end();
-
-        self.drew_mini_map = true;
-    }
-}
-
-impl Drop for Game {
-    fn drop(&mut self) {}
-}
+use crate::generator::{self, GrowingTree, MazeGenerator, Policy, PolicyError};
+use crate::map::{Map, Mask, MaskError, MazeStats, Size, SizeError};
+use crate::player::{Direction, Player};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::cell::OnceCell;
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub type Seed = <ChaCha8Rng as SeedableRng>::Seed;
+
+pub fn random_seed() -> Seed {
+    let mut rng_seed = Seed::default();
+    rand::thread_rng().fill(&mut rng_seed);
+    rng_seed
+}
+
+/// Reads a seed as shown to players, see [`GameState::seed_text`], with or
+/// without the `0x`.
+pub fn parse_seed(text: &str) -> Option<Seed> {
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+    let mut rng_seed = Seed::default();
+    if digits.len() != rng_seed.len() * 2 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    for (i, v) in rng_seed.iter_mut().enumerate() {
+        *v = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(rng_seed)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SettingsError {
+    #[error(transparent)]
+    Size(#[from] SizeError),
+    #[error("Unknown maze generator \"{0}\", expected one of: {names}", names = generator_names())]
+    UnknownGenerator(String),
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+    #[error("A cell selection policy only applies to the growing-tree generator, not {0}")]
+    PolicyWithoutGrowingTree(String),
+    #[error("The share of dead ends to remove must be a percentage, not \"{0}\"")]
+    InvalidBraid(String),
+    #[error(transparent)]
+    Mask(#[from] MaskError),
+    #[error("The number of floors must be from 1 to {MAX_FLOORS}, not \"{0}\"")]
+    InvalidFloors(String),
+    #[error("Unknown placement of the start and goal \"{0}\", expected one of: {names}", names = Endpoints::NAMES.join(", "))]
+    UnknownEndpoints(String),
+    #[error("The seed must be 64 hexadecimal digits as shown in the game, not \"{0}\"")]
+    InvalidSeed(String),
+    #[error("Unknown option \"{0}\"")]
+    UnknownOption(String),
+    #[error("The option \"{0}\" needs a value")]
+    MissingValue(String),
+}
+
+/// The most floors a maze may have.
+pub const MAX_FLOORS: u32 = 16;
+
+fn generator_names() -> String {
+    let names: Vec<_> = generator::GENERATORS.iter().map(|g| g.name()).collect();
+    names.join(", ")
+}
+
+/// Where the start and the goal of a maze are placed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endpoints {
+    /// Wherever the generator put them, mostly the top left and bottom right corners.
+    #[default]
+    Corners,
+    /// The goal as far as possible from the start, see [`Map::place_goal_farthest`].
+    FarthestGoal,
+    /// Both as far apart as possible, see [`Map::place_endpoints_farthest`].
+    Farthest,
+}
+
+impl Endpoints {
+    const NAMES: [&'static str; 3] = ["corners", "farthest-goal", "farthest"];
+    const ALL: [Endpoints; 3] = [
+        Endpoints::Corners,
+        Endpoints::FarthestGoal,
+        Endpoints::Farthest,
+    ];
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| e.name() == name)
+    }
+}
+
+/// What a new game is made of, besides its seed, which only the first game
+/// may be given.
+///
+/// Together with the seed, this reproduces the same maze.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub size: Size,
+    pub generator: Arc<dyn MazeGenerator>,
+    /// The percentage of dead ends removed from the generated maze, see [`Map::braid`].
+    pub braid: u32,
+    /// The shape of the maze, which sets its size instead of `size`.
+    pub mask: Option<Arc<Mask>>,
+    /// Whether the edges of the maze wrap around, see [`Map::wrap`].
+    pub wrap: bool,
+    /// The number of floors, each made like a maze of its own, see [`Map::stacked`].
+    pub floors: u32,
+    /// Where the start and the goal are placed once the floors are stacked.
+    pub endpoints: Endpoints,
+    /// The seed of the first game, to play a maze again, or `None` for a
+    /// random one. The next games always get a random seed.
+    pub seed: Option<Seed>,
+}
+
+impl PartialEq for Settings {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size
+            && *self.generator == *other.generator
+            && self.braid == other.braid
+            && self.mask == other.mask
+            && self.wrap == other.wrap
+            && self.floors == other.floors
+            && self.endpoints == other.endpoints
+            && self.seed == other.seed
+    }
+}
+
+impl Eq for Settings {}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            size: Size::default(),
+            generator: Arc::new(generator::DEFAULT),
+            braid: 0,
+            mask: None,
+            wrap: false,
+            floors: 1,
+            endpoints: Endpoints::Corners,
+            seed: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads command line options, `--size WIDTHxHEIGHT`, `--generator NAME`,
+    /// `--policy POLICY` for the growing tree generator, `--braid PERCENT`,
+    /// `--mask FILE`, `--wrap`, `--floors COUNT`, `--endpoints PLACEMENT` and
+    /// `--seed SEED`, leaving the others at their defaults.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, SettingsError> {
+        let mut settings = Settings::default();
+        let mut policy: Option<Policy> = None;
+        let mut args = args.into_iter();
+        while let Some(option) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| SettingsError::MissingValue(option.clone()))
+            };
+            match option.as_str() {
+                "--size" => settings.size = value()?.parse()?,
+                "--generator" => {
+                    let name = value()?;
+                    let generator =
+                        generator::by_name(&name).ok_or(SettingsError::UnknownGenerator(name))?;
+                    settings.generator = Arc::new(generator);
+                }
+                "--policy" => policy = Some(value()?.parse()?),
+                "--braid" => {
+                    let value = value()?;
+                    settings.braid = match value.parse() {
+                        Ok(percent @ 0..=100) => percent,
+                        _ => return Err(SettingsError::InvalidBraid(value)),
+                    };
+                }
+                "--mask" => settings.mask = Some(Arc::new(Mask::load(&value()?)?)),
+                "--wrap" => settings.wrap = true,
+                "--floors" => {
+                    let value = value()?;
+                    settings.floors = match value.parse() {
+                        Ok(count @ 1..=MAX_FLOORS) => count,
+                        _ => return Err(SettingsError::InvalidFloors(value)),
+                    };
+                }
+                "--endpoints" => {
+                    let name = value()?;
+                    settings.endpoints =
+                        Endpoints::by_name(&name).ok_or(SettingsError::UnknownEndpoints(name))?;
+                }
+                "--seed" => {
+                    let value = value()?;
+                    settings.seed =
+                        Some(parse_seed(&value).ok_or(SettingsError::InvalidSeed(value))?);
+                }
+                _ => return Err(SettingsError::UnknownOption(option)),
+            }
+        }
+
+        if let Some(policy) = policy {
+            let name = settings.generator.name();
+            if name != GrowingTree::DEFAULT.name() {
+                return Err(SettingsError::PolicyWithoutGrowingTree(name.to_owned()));
+            }
+            settings.generator = Arc::new(GrowingTree { policy });
+        }
+        Ok(settings)
+    }
+
+    /// How the maze is made, shown with the seed to reproduce it.
+    pub fn description(&self) -> String {
+        let mut description = self.generator.description();
+        if self.braid > 0 {
+            _ = write!(description, ", braid {}%", self.braid);
+        }
+        if let Some(mask) = &self.mask {
+            _ = write!(description, ", mask {}", mask.name());
+        }
+        if self.wrap {
+            description.push_str(", wrap");
+        }
+        if self.floors > 1 {
+            _ = write!(description, ", {} floors", self.floors);
+        }
+        if self.endpoints != Endpoints::Corners {
+            _ = write!(description, ", {}", self.endpoints.name());
+        }
+        description
+    }
+}
+
+/// The rules of one game, free of any rendering resources.
+///
+/// A state is fully determined by its seed and the actions applied to it,
+/// so it can be driven by bots or replayed without a window.
+pub struct GameState {
+    rng_seed: Seed,
+    rng: ChaCha8Rng,
+    settings: Settings,
+
+    map: Map,
+    player: Player,
+
+    shows_mini_map: bool,
+    is_goal: bool,
+    score: u32,
+
+    mini_map_view_count: u32,
+    key_press_count: u32,
+    /// The fewest key presses reaching the goal, found on first use
+    par: OnceCell<u32>,
+    /// Measured on first use too
+    stats: OnceCell<MazeStats>,
+}
+
+impl GameState {
+    pub fn new(rng_seed: Seed) -> Self {
+        Self::with_settings(rng_seed, Settings::default())
+    }
+
+    pub fn with_settings(rng_seed: Seed, settings: Settings) -> Self {
+        let mut rng = ChaCha8Rng::from_seed(rng_seed);
+
+        let floors = (0..settings.floors)
+            .map(|_| Self::generate_floor(&mut rng, &settings))
+            .collect();
+        let mut map = Map::stacked(&mut rng, floors);
+        match settings.endpoints {
+            Endpoints::Corners => {}
+            Endpoints::FarthestGoal => map.place_goal_farthest(),
+            Endpoints::Farthest => map.place_endpoints_farthest(),
+        }
+        let player = Self::start_player(&map);
+
+        GameState {
+            rng_seed,
+            rng,
+            settings,
+
+            map,
+            player,
+
+            shows_mini_map: false,
+            is_goal: false,
+            score: 0,
+
+            mini_map_view_count: 0,
+            key_press_count: 0,
+            par: OnceCell::new(),
+            stats: OnceCell::new(),
+        }
+    }
+
+    fn start_player(map: &Map) -> Player {
+        Player {
+            x: map.start_x,
+            y: map.start_y,
+            floor: 0,
+            direction: Direction::South,
+            crossing: None,
+        }
+    }
+
+    fn generate_floor(rng: &mut ChaCha8Rng, settings: &Settings) -> Map {
+        let size = settings.mask.as_ref().map_or(settings.size, |m| m.size());
+        let mut map = settings.generator.generate(rng, size);
+        if let Some(mask) = &settings.mask {
+            mask.apply(&mut map, rng);
+        }
+        if settings.wrap {
+            map.wrap(rng);
+        }
+        if settings.braid > 0 {
+            map.braid(rng, settings.braid);
+        }
+        map
+    }
+
+    pub fn with_random_seed() -> Self {
+        Self::new(random_seed())
+    }
+
+    /// Starts the first game, with the seed of the settings if any.
+    pub fn first_game(settings: Settings) -> Self {
+        let rng_seed = settings.seed.unwrap_or_else(random_seed);
+        Self::with_settings(rng_seed, settings)
+    }
+
+    /// Starts another game with a random seed and the same settings.
+    pub fn next_game(&self) -> Self {
+        Self::with_settings(random_seed(), self.settings.clone())
+    }
+
+    pub fn seed(&self) -> &Seed {
+        &self.rng_seed
+    }
+
+    /// The seed as shown to players, e.g. `0x0123...`.
+    pub fn seed_text(&self) -> String {
+        self.rng_seed.iter().fold(String::from("0x"), |mut r, v| {
+            _ = write!(r, "{v:02X}");
+            r
+        })
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    pub fn player(&self) -> &Player {
+        &self.player
+    }
+
+    pub fn shows_mini_map(&self) -> bool {
+        self.shows_mini_map
+    }
+
+    pub fn is_goal(&self) -> bool {
+        self.is_goal
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn mini_map_view_count(&self) -> u32 {
+        self.mini_map_view_count
+    }
+
+    pub fn key_press_count(&self) -> u32 {
+        self.key_press_count
+    }
+
+    /// The fewest key presses taking the player from the start to the goal,
+    /// to compare `key_press_count` with.
+    pub fn par(&self) -> u32 {
+        *self.par.get_or_init(|| {
+            self.map
+                .fewest_key_presses(&Self::start_player(&self.map))
+                .expect("the goal of a generated maze can be reached")
+        })
+    }
+
+    /// How hard the maze is, shown once it is solved.
+    pub fn stats(&self) -> &MazeStats {
+        self.stats.get_or_init(|| self.map.stats())
+    }
+
+    pub fn turn_left(&mut self) {
+        if self.is_goal {
+            return;
+        }
+        self.player.turn_left();
+        self.key_press_count += 1;
+    }
+
+    pub fn turn_right(&mut self) {
+        if self.is_goal {
+            return;
+        }
+        self.player.turn_right();
+        self.key_press_count += 1;
+    }
+
+    pub fn turn_back(&mut self) {
+        if self.is_goal {
+            return;
+        }
+        self.player.turn_back();
+        self.key_press_count += 1;
+    }
+
+    pub fn move_forward(&mut self) {
+        if self.is_goal {
+            return;
+        }
+        if !self.player.move_forward(&self.map) {
+            return;
+        }
+        self.key_press_count += 1;
+
+        let player = &self.player;
+        let map = &self.map;
+        if (player.x, player.y, player.floor) == (map.goal_x, map.goal_y, map.goal_floor) {
+            self.is_goal = true;
+            // Never opening the mini map earns 50 points, otherwise fewer views earn more
+            self.score = 5000 / self.key_press_count
+                + 10u32.checked_div(self.mini_map_view_count).unwrap_or(50)
+                + self.rng.gen_range(0..30);
+        }
+    }
+
+    pub fn toggle_mini_map(&mut self) {
+        if self.is_goal {
+            return;
+        }
+        self.shows_mini_map = !self.shows_mini_map;
+        self.mini_map_view_count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Tile;
+    use proptest::prelude::*;
+
+    fn seed(n: u8) -> Seed {
+        [n; 32]
+    }
+
+    /// Keeps the left hand on the wall until the goal is reached.
+    fn walk_to_goal(state: &mut GameState) {
+        for _ in 0..10_000 {
+            walk_to_goal_step(state);
+        }
+    }
+
+    /// Takes the next step of `walk_to_goal`, or none once at the goal.
+    fn walk_to_goal_step(state: &mut GameState) {
+        if state.is_goal() {
+            return;
+        }
+        state.turn_left();
+        for _ in 0..4 {
+            let before = (state.player().x, state.player().y);
+            state.move_forward();
+            if before != (state.player().x, state.player().y) {
+                break;
+            }
+            state.turn_right();
+        }
+    }
+
+    #[test]
+    fn starts_at_map_start() {
+        let state = GameState::new(seed(1));
+        assert_eq!(state.player().x, state.map().start_x);
+        assert_eq!(state.player().y, state.map().start_y);
+        assert_eq!(state.player().direction, Direction::South);
+        assert!(!state.is_goal());
+        assert_eq!(state.key_press_count(), 0);
+    }
+
+    #[test]
+    fn turns_count_as_key_presses() {
+        let mut state = GameState::new(seed(1));
+        state.turn_left();
+        state.turn_right();
+        state.turn_back();
+        assert_eq!(state.player().direction, Direction::North);
+        assert_eq!(state.key_press_count(), 3);
+    }
+
+    #[test]
+    fn blocked_moves_are_free() {
+        let mut state = GameState::new(seed(1));
+        state.turn_back();
+        state.move_forward();
+        assert_eq!((state.player().x, state.player().y), (1, 1));
+        assert_eq!(state.key_press_count(), 1);
+    }
+
+    #[test]
+    fn mini_map_toggles_are_counted() {
+        let mut state = GameState::new(seed(1));
+        state.toggle_mini_map();
+        assert!(state.shows_mini_map());
+        state.toggle_mini_map();
+        assert!(!state.shows_mini_map());
+        assert_eq!(state.mini_map_view_count(), 2);
+        assert_eq!(state.key_press_count(), 0);
+    }
+
+    #[test]
+    fn reaching_goal_scores_and_freezes() {
+        let mut state = GameState::new(seed(1));
+        walk_to_goal(&mut state);
+        assert!(state.is_goal());
+        assert!(state.score() >= 5000 / state.key_press_count() + 50);
+
+        let presses = state.key_press_count();
+        state.turn_left();
+        state.move_forward();
+        state.toggle_mini_map();
+        assert_eq!(state.key_press_count(), presses);
+        assert!(!state.shows_mini_map());
+    }
+
+    #[test]
+    fn par_is_never_beaten() {
+        for seed_value in 0..5 {
+            let mut state = GameState::new(seed(seed_value));
+            let par = state.par();
+            assert!(par > 0);
+            assert!(state.stats().solution_length.unwrap() <= par);
+            walk_to_goal(&mut state);
+            assert!(state.key_press_count() >= par);
+            assert_eq!(state.par(), par);
+        }
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let mut a = GameState::new(seed(7));
+        let mut b = GameState::new(seed(7));
+        walk_to_goal(&mut a);
+        walk_to_goal(&mut b);
+        assert_eq!(a.key_press_count(), b.key_press_count());
+        assert_eq!(a.score(), b.score());
+    }
+
+    #[test]
+    fn seed_text_is_hex() {
+        let state = GameState::new(seed(0xAB));
+        assert_eq!(state.seed_text(), format!("0x{}", "AB".repeat(32)));
+    }
+
+    #[test]
+    fn replays_the_seed_shown() {
+        let state = GameState::new(seed(0xAB));
+        let args = ["--seed".to_owned(), state.seed_text()];
+        let settings = Settings::from_args(args).unwrap();
+        assert_eq!(settings.seed, Some(seed(0xAB)));
+        let replay = GameState::first_game(settings);
+        assert_eq!(replay.seed(), state.seed());
+        assert_eq!(replay.map(), state.map());
+        assert_ne!(replay.next_game().seed(), state.seed());
+
+        let args = ["--seed".to_owned(), "ab".repeat(32)];
+        assert_eq!(Settings::from_args(args).unwrap().seed, Some(seed(0xAB)));
+    }
+
+    #[test]
+    fn reaches_the_goal_on_the_last_floor() {
+        let settings = Settings {
+            generator: Arc::new(generator::Backtracker),
+            floors: 3,
+            ..Default::default()
+        };
+        let mut state = GameState::with_settings(seed(4), settings);
+        assert_eq!((state.map().floors, state.map().goal_floor), (3, 2));
+        let mut floors_seen = [false; 3];
+        for _ in 0..10_000 {
+            floors_seen[state.player().floor as usize] = true;
+            walk_to_goal_step(&mut state);
+        }
+        assert!(state.is_goal());
+        assert_eq!(state.player().floor, 2);
+        assert_eq!(floors_seen, [true; 3]);
+    }
+
+    #[test]
+    fn places_the_endpoints_far_apart() {
+        let length = |endpoints| {
+            let settings = Settings {
+                endpoints,
+                ..Default::default()
+            };
+            let mut state = GameState::with_settings(seed(3), settings);
+            let map = state.map();
+            let length = map.shortest_path(map.start()).unwrap().len();
+            walk_to_goal(&mut state);
+            assert!(state.is_goal());
+            length
+        };
+        let corners = length(Endpoints::Corners);
+        let farthest_goal = length(Endpoints::FarthestGoal);
+        assert!(farthest_goal >= corners);
+        assert!(length(Endpoints::Farthest) >= farthest_goal);
+    }
+
+    #[test]
+    fn uses_the_size_from_settings() {
+        let settings = Settings {
+            size: Size::new(41, 15).unwrap(),
+            ..Default::default()
+        };
+        let mut state = GameState::with_settings(seed(2), settings.clone());
+        assert_eq!((state.map().width, state.map().height), (41, 15));
+        assert_eq!((state.map().goal_x, state.map().goal_y), (39, 13));
+        walk_to_goal(&mut state);
+        assert!(state.is_goal());
+        assert_eq!(state.next_game().settings(), &settings);
+    }
+
+    #[test]
+    fn reads_settings_from_args() {
+        let args = |s: &str| s.split_whitespace().map(String::from).collect::<Vec<_>>();
+        let settings = |s: &str| Settings::from_args(args(s)).unwrap();
+        let error = |s: &str| Settings::from_args(args(s)).unwrap_err();
+        assert_eq!(settings(""), Settings::default());
+        assert_eq!(
+            settings("--generator bar-knocking --size 31x11"),
+            Settings {
+                size: Size::new(31, 11).unwrap(),
+                generator: Arc::new(generator::BarKnocking),
+                braid: 0,
+                mask: None,
+                wrap: false,
+                floors: 1,
+                endpoints: Endpoints::Corners,
+                seed: None,
+            }
+        );
+        assert!(matches!(
+            error("--size 4x4"),
+            SettingsError::Size(SizeError::TooSmall(4, 4))
+        ));
+        assert!(matches!(
+            error("--generator nope"),
+            SettingsError::UnknownGenerator(name) if name == "nope"
+        ));
+        assert!(matches!(
+            error("--size"),
+            SettingsError::MissingValue(option) if option == "--size"
+        ));
+        assert_eq!(
+            settings("--policy newest=1,random=3 --generator growing-tree"),
+            Settings {
+                generator: Arc::new(GrowingTree {
+                    policy: "newest=1,random=3".parse().unwrap()
+                }),
+                ..Default::default()
+            }
+        );
+        assert!(matches!(
+            error("--generator growing-tree --policy newest=4294967295,random=1"),
+            SettingsError::Policy(PolicyError::TooHeavy)
+        ));
+        assert!(matches!(
+            error("--policy oldest"),
+            SettingsError::PolicyWithoutGrowingTree(name) if name == "bar-knocking"
+        ));
+        assert_eq!(
+            settings("--braid 30").description(),
+            "bar-knocking, braid 30%"
+        );
+        assert_eq!(
+            settings("--wrap --braid 5").description(),
+            "bar-knocking, braid 5%, wrap"
+        );
+        assert!(matches!(
+            error("--braid 101"),
+            SettingsError::InvalidBraid(value) if value == "101"
+        ));
+        assert_eq!(
+            settings("--floors 3").description(),
+            "bar-knocking, 3 floors"
+        );
+        assert!(matches!(
+            error("--floors 0"),
+            SettingsError::InvalidFloors(value) if value == "0"
+        ));
+        assert_eq!(
+            settings("--endpoints farthest").description(),
+            "bar-knocking, farthest"
+        );
+        assert!(matches!(
+            error("--endpoints middle"),
+            SettingsError::UnknownEndpoints(name) if name == "middle"
+        ));
+        assert!(matches!(
+            error("--mask no/such/file.txt"),
+            SettingsError::Mask(MaskError::Read { .. })
+        ));
+        assert!(matches!(
+            error("--seed 0x12"),
+            SettingsError::InvalidSeed(value) if value == "0x12"
+        ));
+        assert!(matches!(
+            error(&format!("--seed 0x{}", "+1".repeat(32))),
+            SettingsError::InvalidSeed(_)
+        ));
+        assert!(matches!(
+            error("31x11"),
+            SettingsError::UnknownOption(option) if option == "31x11"
+        ));
+    }
+
+    #[test]
+    fn shapes_the_maze_with_the_mask() {
+        let mask = Mask::from_ascii("cross", ".XX.\nXXXX\nXXXX\n.XX.").unwrap();
+        let settings = Settings {
+            mask: Some(Arc::new(mask)),
+            ..Default::default()
+        };
+        assert_eq!(settings.description(), "bar-knocking, mask cross");
+        let mut state = GameState::with_settings(seed(3), settings);
+        assert_eq!((state.map().width, state.map().height), (9, 9));
+        assert_eq!(state.map().get(1, 1), Some(Tile::Wall));
+        walk_to_goal(&mut state);
+        assert!(state.is_goal());
+    }
+
+    /// No mask, or the cells a random walk goes through from the top left
+    /// corner, which are always connected.
+    fn masks() -> impl Strategy<Value = Option<Arc<Mask>>> {
+        let walks = (2..=12u32, 2..=12u32).prop_flat_map(|(width, height)| {
+            (
+                Just(width),
+                Just(height),
+                prop::collection::vec(0..4u8, 0..100),
+            )
+        });
+        prop_oneof![
+            Just(None),
+            walks.prop_map(|(width, height, steps)| {
+                let mut cells = vec![false; (width * height) as usize];
+                let (mut x, mut y) = (1u32, 0u32);
+                cells[0] = true;
+                cells[1] = true;
+                for step in steps {
+                    match step {
+                        0 => x = x.saturating_sub(1),
+                        1 => x = (x + 1).min(width - 1),
+                        2 => y = y.saturating_sub(1),
+                        _ => y = (y + 1).min(height - 1),
+                    }
+                    cells[(x + y * width) as usize] = true;
+                }
+                Some(Arc::new(Mask::new("walk", width, height, cells).unwrap()))
+            }),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn makes_valid_mazes_with_any_settings(
+            rng_seed: Seed,
+            columns in 2..=15u32,
+            rows in 2..=15u32,
+            braid in prop_oneof![Just(0), 1..=100u32],
+            mask in masks(),
+            wrap: bool,
+            floors in 1..=3u32,
+            endpoints in prop::sample::select(Endpoints::ALL.to_vec()),
+        ) {
+            for generator in generator::GENERATORS {
+                let settings = Settings {
+                    size: Size::new(columns * 2 + 1, rows * 2 + 1).unwrap(),
+                    generator: Arc::new(generator),
+                    braid,
+                    mask: mask.clone(),
+                    wrap,
+                    floors,
+                    endpoints,
+                    seed: None,
+                };
+                let description = settings.description();
+                let state = GameState::with_settings(rng_seed, settings);
+                let validation = if generator.is_perfect() && braid == 0 && !wrap {
+                    state.map().validate_perfect()
+                } else {
+                    state.map().validate()
+                };
+                prop_assert_eq!(validation, Ok(()), "{}", description);
+            }
+        }
+    }
+}
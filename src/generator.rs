@@ -0,0 +1,185 @@
+//! Algorithms carving a [`Map`] out of walls.
+//!
+//! Every generator is deterministic for a given random number generator
+//! state, so a seed and a generator name are enough to reproduce a maze.
+
+mod backtracker;
+mod bar_knocking;
+mod eller;
+mod growing_tree;
+mod kruskal;
+mod prim;
+mod rooms;
+mod weave;
+mod wilson;
+
+pub use backtracker::Backtracker;
+pub use bar_knocking::BarKnocking;
+pub use eller::{Eller, EllerRows};
+pub use growing_tree::{GrowingTree, Policy, PolicyError};
+pub use kruskal::Kruskal;
+pub use prim::Prim;
+pub use rooms::Rooms;
+pub use weave::Weave;
+pub use wilson::Wilson;
+
+use crate::map::{Map, Size, Tile};
+use rand::RngCore;
+use std::fmt;
+
+pub trait MazeGenerator: Send + Sync {
+    /// The name the generator is registered under, in kebab case.
+    fn name(&self) -> &'static str;
+
+    /// The name followed by the parameters of the generator, if any.
+    fn description(&self) -> String {
+        self.name().to_owned()
+    }
+
+    /// Whether there is a single way between any two cells of the mazes
+    /// made, see [`Map::validate_perfect`].
+    fn is_perfect(&self) -> bool {
+        true
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore, size: Size) -> Map;
+}
+
+impl<G: MazeGenerator + ?Sized> MazeGenerator for &G {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn description(&self) -> String {
+        (**self).description()
+    }
+
+    fn is_perfect(&self) -> bool {
+        (**self).is_perfect()
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore, size: Size) -> Map {
+        (**self).generate(rng, size)
+    }
+}
+
+impl fmt::Debug for dyn MazeGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.description())
+    }
+}
+
+/// Generators are told apart by their names and parameters.
+impl PartialEq for dyn MazeGenerator {
+    fn eq(&self, other: &Self) -> bool {
+        self.description() == other.description()
+    }
+}
+
+impl Eq for dyn MazeGenerator {}
+
+/// Every generator that can be chosen by name.
+pub const GENERATORS: &[&dyn MazeGenerator] = &[
+    &BarKnocking,
+    &Backtracker,
+    &Kruskal,
+    &Prim,
+    &Wilson,
+    &Eller,
+    &GrowingTree::DEFAULT,
+    &Rooms,
+    &Weave,
+];
+
+/// The generator of the original game.
+pub const DEFAULT: &dyn MazeGenerator = &BarKnocking;
+
+pub fn by_name(name: &str) -> Option<&'static dyn MazeGenerator> {
+    GENERATORS.iter().copied().find(|g| g.name() == name)
+}
+
+/// A cell of a maze, with corridors on odd coordinates of the map and walls
+/// or passages between them. Cell `(x, y)` is the tile `(2x + 1, 2y + 1)`.
+type Cell = (u32, u32);
+
+/// The number of cells on each axis of a map of `size`.
+fn cell_grid(size: Size) -> (u32, u32) {
+    ((size.width() - 1) / 2, (size.height() - 1) / 2)
+}
+
+/// The cells next to `cell` in a grid of `grid` cells, in no random order.
+fn neighbors((x, y): Cell, grid: (u32, u32)) -> impl Iterator<Item = Cell> {
+    [
+        x.checked_sub(1).map(|x| (x, y)),
+        y.checked_sub(1).map(|y| (x, y)),
+        (x + 1 < grid.0).then_some((x + 1, y)),
+        (y + 1 < grid.1).then_some((x, y + 1)),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+fn open_cell(map: &mut Map, (x, y): Cell) {
+    map.set(x * 2 + 1, y * 2 + 1, Tile::Floor);
+}
+
+/// Opens both cells and the wall between them, which must be neighbors.
+fn open_passage(map: &mut Map, a: Cell, b: Cell) {
+    open_cell(map, a);
+    open_cell(map, b);
+    map.set(a.0 + b.0 + 1, a.1 + b.1 + 1, Tile::Floor);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MAX_SIZE;
+    use proptest::prelude::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn names_are_unique() {
+        for (i, a) in GENERATORS.iter().enumerate() {
+            assert_eq!(by_name(a.name()), Some(*a));
+            assert!(GENERATORS[i + 1..].iter().all(|b| a.name() != b.name()));
+        }
+        assert_eq!(by_name("no-such-generator"), None);
+    }
+
+    #[test]
+    fn handles_the_largest_size() {
+        let size = Size::new(MAX_SIZE, MAX_SIZE).unwrap();
+        for generator in GENERATORS {
+            let mut rng = ChaCha8Rng::seed_from_u64(0);
+            let map = generator.generate(&mut rng, size);
+            let validation = if generator.is_perfect() {
+                map.validate_perfect()
+            } else {
+                map.validate()
+            };
+            assert_eq!(validation, Ok(()), "{}", generator.name());
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+
+        #[test]
+        fn generates_valid_mazes(seed: u64, columns in 2..=20u32, rows in 2..=20u32) {
+            let size = Size::new(columns * 2 + 1, rows * 2 + 1).unwrap();
+            for generator in GENERATORS {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                let map = generator.generate(&mut rng, size);
+                let validation = if generator.is_perfect() {
+                    map.validate_perfect()
+                } else {
+                    map.validate()
+                };
+                prop_assert_eq!(validation, Ok(()), "{} at {}x{}", generator.name(), size.width(), size.height());
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                prop_assert_eq!(&generator.generate(&mut rng, size), &map, "{} is not reproducible", generator.name());
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use super::{cell_grid, neighbors, open_cell, open_passage, Cell, MazeGenerator};
+use crate::map::{Map, Size, Tile};
+use rand::{seq::SliceRandom, Rng, RngCore};
+
+/// Walks randomly to unvisited cells, backing up on dead ends, which makes
+/// long winding passages with few branches.
+///
+/// The path is kept on an explicit stack instead of recursing, since it can
+/// be as long as the whole maze.
+pub struct Backtracker;
+
+impl MazeGenerator for Backtracker {
+    fn name(&self) -> &'static str {
+        "backtracker"
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore, size: Size) -> Map {
+        let grid = cell_grid(size);
+        let mut map = Map::filled(size, Tile::Wall);
+        let mut visited = vec![false; (grid.0 * grid.1) as usize];
+        let index = |(x, y): Cell| (x + y * grid.0) as usize;
+
+        let first = (rng.gen_range(0..grid.0), rng.gen_range(0..grid.1));
+        visited[index(first)] = true;
+        open_cell(&mut map, first);
+        let mut path = vec![first];
+
+        while let Some(&cell) = path.last() {
+            let unvisited: Vec<Cell> = neighbors(cell, grid)
+                .filter(|&n| !visited[index(n)])
+                .collect();
+            match unvisited.choose(rng) {
+                Some(&next) => {
+                    visited[index(next)] = true;
+                    open_passage(&mut map, cell, next);
+                    path.push(next);
+                }
+                None => {
+                    path.pop();
+                }
+            }
+        }
+
+        map
+    }
+}
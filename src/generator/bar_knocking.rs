@@ -0,0 +1,62 @@
+use super::MazeGenerator;
+use crate::map::{Map, Size, Tile};
+use rand::{Rng, RngCore};
+
+/// Lets each pillar fall over, turning one of the floors next to it into
+/// a wall; the algorithm of the original game.
+///
+/// Pillars of the first column may fall in any direction, the others not to
+/// the left. Nothing stops two of them from falling onto the same wall, so
+/// the maze may have loops.
+pub struct BarKnocking;
+
+impl MazeGenerator for BarKnocking {
+    fn name(&self) -> &'static str {
+        "bar-knocking"
+    }
+
+    fn is_perfect(&self) -> bool {
+        false
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore, size: Size) -> Map {
+        let (width, height) = (size.width(), size.height());
+        let mut map = Map::filled(size, Tile::Wall);
+
+        for y in 1..(height - 1) {
+            for x in 1..(width - 1) {
+                if x % 2 != 1 && y % 2 != 1 {
+                    continue;
+                }
+                map.set(x, y, Tile::Floor);
+            }
+        }
+
+        for y in (2..(height - 2)).step_by(2) {
+            let x = 2u32;
+            let is_horizontal: bool = rng.gen();
+            if is_horizontal {
+                let new_x: u32 = if rng.gen() { x + 1 } else { x - 1 };
+                map.set(new_x, y, Tile::Wall);
+            } else {
+                let new_y: u32 = if rng.gen() { y + 1 } else { y - 1 };
+                map.set(x, new_y, Tile::Wall);
+            }
+        }
+
+        for x in (4..(width - 2)).step_by(2) {
+            for y in (2..(height - 2)).step_by(2) {
+                let is_horizontal: bool = rng.gen();
+                if is_horizontal {
+                    let new_x: u32 = x + 1;
+                    map.set(new_x, y, Tile::Wall);
+                } else {
+                    let new_y: u32 = if rng.gen() { y + 1 } else { y - 1 };
+                    map.set(x, new_y, Tile::Wall);
+                }
+            }
+        }
+
+        map
+    }
+}
@@ -0,0 +1,143 @@
+use super::MazeGenerator;
+use crate::map::{DisjointSet, Map, Size, Tile};
+use rand::{Rng, RngCore};
+use std::collections::VecDeque;
+
+/// Builds the maze one row of cells at a time, keeping only which cells of
+/// the current row are already connected.
+///
+/// [`EllerRows`] streams the same mazes row by row, for sizes too large to
+/// fit in a [`Map`].
+pub struct Eller;
+
+impl MazeGenerator for Eller {
+    fn name(&self) -> &'static str {
+        "eller"
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore, size: Size) -> Map {
+        let mut map = Map::filled(size, Tile::Wall);
+        for (y, row) in EllerRows::new(rng, size).enumerate() {
+            for (x, tile) in row.into_iter().enumerate() {
+                map.set(x as u32, y as u32, tile);
+            }
+        }
+        map
+    }
+}
+
+/// The tiles of a maze made by [`Eller`], from the top row to the bottom
+/// one, using memory proportional to the width only.
+pub struct EllerRows<R> {
+    rng: R,
+    /// The width of a row of tiles
+    width: u32,
+    /// The number of rows of cells left to make
+    rows_left: u32,
+    /// The set of each cell of the current row, in `0..sets.len()`
+    sets: Vec<usize>,
+    /// Rows of tiles made but not returned yet
+    pending: VecDeque<Vec<Tile>>,
+}
+
+impl<R: Rng> EllerRows<R> {
+    pub fn new(rng: R, size: Size) -> Self {
+        let cell_width = ((size.width() - 1) / 2) as usize;
+        EllerRows {
+            rng,
+            width: size.width(),
+            rows_left: (size.height() - 1) / 2,
+            sets: (0..cell_width).collect(),
+            pending: VecDeque::from([vec![Tile::Wall; size.width() as usize]]),
+        }
+    }
+
+    /// Carves the current row of cells and the passages down from it, and
+    /// moves on to the next row.
+    fn make_row(&mut self) {
+        let cell_width = self.sets.len();
+        let is_last = self.rows_left == 1;
+        self.rows_left -= 1;
+
+        let mut cells = vec![Tile::Wall; self.width as usize];
+        let mut below = vec![Tile::Wall; self.width as usize];
+        let mut joined = DisjointSet::new(cell_width);
+        for x in 0..cell_width {
+            cells[x * 2 + 1] = Tile::Floor;
+            if x + 1 < cell_width
+                && !joined.is_same(self.sets[x], self.sets[x + 1])
+                && (is_last || self.rng.gen())
+            {
+                joined.union(self.sets[x], self.sets[x + 1]);
+                cells[x * 2 + 2] = Tile::Floor;
+            }
+        }
+        self.pending.push_back(cells);
+        if is_last {
+            self.pending.push_back(below);
+            return;
+        }
+
+        // Every set goes down through one of its cells at least
+        let mut members: Vec<Vec<usize>> = vec![Vec::new(); cell_width];
+        for x in 0..cell_width {
+            let set = joined.find(self.sets[x]);
+            members[set].push(x);
+        }
+        let mut is_used = vec![false; cell_width];
+        let mut goes_down = vec![false; cell_width];
+        for (set, xs) in members.iter().enumerate() {
+            if xs.is_empty() {
+                continue;
+            }
+            let required = self.rng.gen_range(0..xs.len());
+            for (i, &x) in xs.iter().enumerate() {
+                if i == required || self.rng.gen() {
+                    goes_down[x] = true;
+                    below[x * 2 + 1] = Tile::Floor;
+                    self.sets[x] = set;
+                    is_used[set] = true;
+                }
+            }
+        }
+        self.pending.push_back(below);
+
+        // The other cells of the next row start sets of their own
+        let mut unused = (0..cell_width).filter(|&set| !is_used[set]);
+        for (set, goes_down) in self.sets.iter_mut().zip(goes_down) {
+            if !goes_down {
+                *set = unused.next().expect("a row has as many sets as cells");
+            }
+        }
+    }
+}
+
+impl<R: Rng> Iterator for EllerRows<R> {
+    type Item = Vec<Tile>;
+
+    fn next(&mut self) -> Option<Vec<Tile>> {
+        if self.pending.is_empty() && self.rows_left > 0 {
+            self.make_row();
+        }
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn streams_rows_beyond_the_map_size() {
+        let size = Size::unbounded(5001, 7).unwrap();
+        let rows = EllerRows::new(ChaCha8Rng::seed_from_u64(0), size);
+        let mut count = 0;
+        for row in rows {
+            assert_eq!(row.len(), 5001);
+            count += 1;
+        }
+        assert_eq!(count, 7);
+    }
+}
@@ -0,0 +1,299 @@
+use super::{cell_grid, neighbors, open_cell, open_passage, Cell, MazeGenerator};
+use crate::map::{Map, Size, Tile};
+use rand::{seq::SliceRandom, Rng, RngCore};
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum PolicyError {
+    #[error("Unknown cell selection \"{0}\", expected newest, random or oldest")]
+    UnknownSelection(String),
+    #[error("Cannot read a weight from \"{0}\"")]
+    MalformedWeight(String),
+    #[error("A policy needs a selection with a weight above zero")]
+    NoWeight,
+    #[error("The weights of a policy must add up to {} at most", u32::MAX)]
+    TooHeavy,
+}
+
+/// How [`GrowingTree`] picks the cell to grow from, as relative weights of
+/// picking the newest, a random or the oldest cell still growing.
+///
+/// Always picking the newest cell makes mazes like [`super::Backtracker`],
+/// and a random one mazes like [`super::Prim`]. The weights must add up to
+/// a `u32`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Policy {
+    pub newest: u32,
+    pub random: u32,
+    pub oldest: u32,
+}
+
+impl Policy {
+    pub const NEWEST: Policy = Policy {
+        newest: 1,
+        random: 0,
+        oldest: 0,
+    };
+    pub const RANDOM: Policy = Policy {
+        newest: 0,
+        random: 1,
+        oldest: 0,
+    };
+    pub const OLDEST: Policy = Policy {
+        newest: 0,
+        random: 0,
+        oldest: 1,
+    };
+
+    /// The sum of the weights, or `None` if it overflows.
+    fn total(&self) -> Option<u32> {
+        self.newest
+            .checked_add(self.random)?
+            .checked_add(self.oldest)
+    }
+
+    /// Picks one of the selections by their weights.
+    fn select(&self, rng: &mut dyn RngCore) -> Selection {
+        let total = self.total().expect("the weights add up to a u32");
+        let roll = rng.gen_range(0..total);
+        if roll < self.newest {
+            Selection::Newest
+        } else if roll - self.newest < self.random {
+            Selection::Random
+        } else {
+            Selection::Oldest
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Selection {
+    Newest,
+    Random,
+    Oldest,
+}
+
+/// Mixes the selections as `newest=75,random=25`, where a selection alone
+/// weighs 1.
+impl FromStr for Policy {
+    type Err = PolicyError;
+
+    fn from_str(s: &str) -> Result<Self, PolicyError> {
+        let mut policy = Policy {
+            newest: 0,
+            random: 0,
+            oldest: 0,
+        };
+        for part in s.split(',') {
+            let (name, weight) = match part.split_once('=') {
+                Some((name, weight)) => {
+                    let weight = weight
+                        .trim()
+                        .parse()
+                        .map_err(|_| PolicyError::MalformedWeight(part.to_owned()))?;
+                    (name.trim(), weight)
+                }
+                None => (part.trim(), 1),
+            };
+            let selection = match name {
+                "newest" => &mut policy.newest,
+                "random" => &mut policy.random,
+                "oldest" => &mut policy.oldest,
+                _ => return Err(PolicyError::UnknownSelection(name.to_owned())),
+            };
+            *selection = selection.checked_add(weight).ok_or(PolicyError::TooHeavy)?;
+        }
+        match policy.total() {
+            None => Err(PolicyError::TooHeavy),
+            Some(0) => Err(PolicyError::NoWeight),
+            Some(_) => Ok(policy),
+        }
+    }
+}
+
+/// Writes the form read by `from_str`, leaving out unused selections.
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let selections = [
+            ("newest", self.newest),
+            ("random", self.random),
+            ("oldest", self.oldest),
+        ];
+        let mut is_first = true;
+        for (name, weight) in selections.into_iter().filter(|(_, w)| *w > 0) {
+            if !is_first {
+                f.write_str(",")?;
+            }
+            write!(f, "{name}={weight}")?;
+            is_first = false;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps a list of cells to grow the maze from, taking them by its policy
+/// and dropping them when they have no unvisited neighbor left.
+///
+/// Dropped cells are only marked, and left in the list until they reach
+/// one of its ends or make up half of it, so that dropping a cell from the
+/// middle takes amortized constant time whatever the policy.
+pub struct GrowingTree {
+    pub policy: Policy,
+}
+
+impl GrowingTree {
+    pub const DEFAULT: GrowingTree = GrowingTree {
+        policy: Policy {
+            newest: 75,
+            random: 25,
+            oldest: 0,
+        },
+    };
+}
+
+impl MazeGenerator for GrowingTree {
+    fn name(&self) -> &'static str {
+        "growing-tree"
+    }
+
+    fn description(&self) -> String {
+        format!("{} {}", self.name(), self.policy)
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore, size: Size) -> Map {
+        let grid = cell_grid(size);
+        let mut map = Map::filled(size, Tile::Wall);
+        let mut visited = vec![false; (grid.0 * grid.1) as usize];
+        let index = |(x, y): Cell| (x + y * grid.0) as usize;
+
+        let first = (rng.gen_range(0..grid.0), rng.gen_range(0..grid.1));
+        visited[index(first)] = true;
+        open_cell(&mut map, first);
+        let mut active = VecDeque::from([first]);
+        let mut dropped = vec![false; visited.len()];
+        let mut growing = 1;
+
+        while growing > 0 {
+            while active.front().is_some_and(|&cell| dropped[index(cell)]) {
+                active.pop_front();
+            }
+            while active.back().is_some_and(|&cell| dropped[index(cell)]) {
+                active.pop_back();
+            }
+            let cell = match self.policy.select(rng) {
+                Selection::Newest => active[active.len() - 1],
+                Selection::Oldest => active[0],
+                // At least half of the cells are still growing, so this takes two tries on average
+                Selection::Random => loop {
+                    let cell = active[rng.gen_range(0..active.len())];
+                    if !dropped[index(cell)] {
+                        break cell;
+                    }
+                },
+            };
+            let unvisited: Vec<Cell> = neighbors(cell, grid)
+                .filter(|&n| !visited[index(n)])
+                .collect();
+            match unvisited.choose(rng) {
+                Some(&next) => {
+                    visited[index(next)] = true;
+                    open_passage(&mut map, cell, next);
+                    active.push_back(next);
+                    growing += 1;
+                }
+                None => {
+                    dropped[index(cell)] = true;
+                    growing -= 1;
+                    if growing * 2 < active.len() {
+                        active.retain(|&cell| !dropped[index(cell)]);
+                    }
+                }
+            }
+        }
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn generate(policy: Policy, seed: u64, width: u32, height: u32) -> Map {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        GrowingTree { policy }.generate(&mut rng, Size::new(width, height).unwrap())
+    }
+
+    #[test]
+    fn makes_perfect_mazes_with_any_policy() {
+        for policy in [Policy::NEWEST, Policy::RANDOM, Policy::OLDEST] {
+            for seed in 0..10 {
+                assert_eq!(generate(policy, seed, 31, 21).validate_perfect(), Ok(()));
+            }
+            assert_eq!(generate(policy, 0, 5, 5).validate_perfect(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn policy_sets_the_texture() {
+        // Backtracking makes few dead ends, random picks many
+        let dead_ends = |policy| generate(policy, 0, 101, 101).dead_ends().len();
+        let newest = dead_ends(Policy::NEWEST);
+        let mixed = dead_ends("newest=1,random=1".parse().unwrap());
+        let random = dead_ends(Policy::RANDOM);
+        assert!(
+            newest < mixed && mixed < random,
+            "{newest} {mixed} {random}"
+        );
+    }
+
+    #[test]
+    fn parses_policies() {
+        assert_eq!("newest".parse(), Ok(Policy::NEWEST));
+        assert_eq!(
+            "newest=75, random=25".parse(),
+            Ok(GrowingTree::DEFAULT.policy)
+        );
+        assert_eq!(
+            "oldest,random=2".parse(),
+            Ok(Policy {
+                newest: 0,
+                random: 2,
+                oldest: 1
+            })
+        );
+        assert_eq!(
+            "first".parse::<Policy>(),
+            Err(PolicyError::UnknownSelection(String::from("first")))
+        );
+        assert_eq!(
+            "newest=x".parse::<Policy>(),
+            Err(PolicyError::MalformedWeight(String::from("newest=x")))
+        );
+        assert_eq!("random=0".parse::<Policy>(), Err(PolicyError::NoWeight));
+        assert_eq!(
+            "newest=4294967295,random=1".parse::<Policy>(),
+            Err(PolicyError::TooHeavy)
+        );
+        assert_eq!(
+            "oldest=4294967295,oldest".parse::<Policy>(),
+            Err(PolicyError::TooHeavy)
+        );
+        assert_eq!(
+            "newest=4294967295".parse(),
+            Ok(Policy {
+                newest: u32::MAX,
+                random: 0,
+                oldest: 0
+            })
+        );
+
+        let policy = GrowingTree::DEFAULT.policy;
+        assert_eq!(policy.to_string(), "newest=75,random=25");
+        assert_eq!(policy.to_string().parse(), Ok(policy));
+    }
+}
@@ -0,0 +1,41 @@
+use super::{cell_grid, open_passage, Cell, MazeGenerator};
+use crate::map::{DisjointSet, Map, Size, Tile};
+use rand::{seq::SliceRandom, RngCore};
+
+/// Opens the walls between cells in random order, skipping those between
+/// cells already connected, which makes many short dead ends all over.
+pub struct Kruskal;
+
+impl MazeGenerator for Kruskal {
+    fn name(&self) -> &'static str {
+        "kruskal"
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore, size: Size) -> Map {
+        let grid = cell_grid(size);
+        let mut map = Map::filled(size, Tile::Wall);
+        let index = |(x, y): Cell| (x + y * grid.0) as usize;
+
+        let mut walls: Vec<(Cell, Cell)> = Vec::new();
+        for y in 0..grid.1 {
+            for x in 0..grid.0 {
+                if x + 1 < grid.0 {
+                    walls.push(((x, y), (x + 1, y)));
+                }
+                if y + 1 < grid.1 {
+                    walls.push(((x, y), (x, y + 1)));
+                }
+            }
+        }
+        walls.shuffle(rng);
+
+        let mut sets = DisjointSet::new((grid.0 * grid.1) as usize);
+        for (a, b) in walls {
+            if sets.union(index(a), index(b)) {
+                open_passage(&mut map, a, b);
+            }
+        }
+
+        map
+    }
+}
@@ -0,0 +1,58 @@
+use super::{cell_grid, neighbors, open_cell, open_passage, Cell, MazeGenerator};
+use crate::map::{Map, Size, Tile};
+use rand::{seq::SliceRandom, Rng, RngCore};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Outside,
+    Frontier,
+    Inside,
+}
+
+/// Grows the maze from one cell by attaching random cells of its frontier,
+/// which makes many short dead ends radiating from the first cell.
+pub struct Prim;
+
+impl MazeGenerator for Prim {
+    fn name(&self) -> &'static str {
+        "prim"
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore, size: Size) -> Map {
+        let grid = cell_grid(size);
+        let mut map = Map::filled(size, Tile::Wall);
+        let mut states = vec![State::Outside; (grid.0 * grid.1) as usize];
+        let index = |(x, y): Cell| (x + y * grid.0) as usize;
+
+        let mut frontier = Vec::new();
+        let first = (rng.gen_range(0..grid.0), rng.gen_range(0..grid.1));
+        open_cell(&mut map, first);
+        attach(first, grid, &mut states, &mut frontier);
+
+        while !frontier.is_empty() {
+            let cell = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+            let inside: Vec<Cell> = neighbors(cell, grid)
+                .filter(|&n| states[index(n)] == State::Inside)
+                .collect();
+            let &to = inside
+                .choose(rng)
+                .expect("frontier cells are next to the maze");
+            open_passage(&mut map, cell, to);
+            attach(cell, grid, &mut states, &mut frontier);
+        }
+
+        map
+    }
+}
+
+/// Puts `cell` inside the maze and its outside neighbors on the frontier.
+fn attach(cell: Cell, grid: (u32, u32), states: &mut [State], frontier: &mut Vec<Cell>) {
+    let index = |(x, y): Cell| (x + y * grid.0) as usize;
+    states[index(cell)] = State::Inside;
+    for n in neighbors(cell, grid) {
+        if states[index(n)] == State::Outside {
+            states[index(n)] = State::Frontier;
+            frontier.push(n);
+        }
+    }
+}
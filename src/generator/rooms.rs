@@ -0,0 +1,241 @@
+use super::{cell_grid, open_cell, open_passage, Cell, MazeGenerator};
+use crate::map::{DisjointSet, Map, Size, Tile};
+use rand::{seq::SliceRandom, Rng, RngCore};
+
+/// The smallest and largest sides of a room, in cells.
+const MIN_ROOM_SIDE: u32 = 2;
+const MAX_ROOM_SIDE: u32 = 5;
+
+/// A rectangle of cells opened as a whole.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Room {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Room {
+    /// Whether the rooms overlap or touch, so that a corridor fits between
+    /// rooms that do not.
+    fn is_near(&self, other: &Room) -> bool {
+        self.x <= other.x + other.width
+            && other.x <= self.x + self.width
+            && self.y <= other.y + other.height
+            && other.y <= self.y + self.height
+    }
+
+    fn cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+/// Places rectangular rooms apart from each other and joins them with
+/// corridors in the style of [`super::Kruskal`], starting in one room and
+/// ending in another.
+///
+/// Each room is entered from the corridors at least once, but may have more
+/// doors, so there can be several ways through it. Maps too small for two
+/// rooms keep the start and the goal in their corners.
+pub struct Rooms;
+
+impl MazeGenerator for Rooms {
+    fn name(&self) -> &'static str {
+        "rooms"
+    }
+
+    fn is_perfect(&self) -> bool {
+        false
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore, size: Size) -> Map {
+        let grid = cell_grid(size);
+        let mut map = Map::filled(size, Tile::Wall);
+        let index = |(x, y): Cell| (x + y * grid.0) as usize;
+        let mut sets = DisjointSet::new((grid.0 * grid.1) as usize);
+
+        let rooms = place_rooms(rng, grid);
+        for room in &rooms {
+            for (x, y) in room.cells() {
+                open_cell(&mut map, (x, y));
+                if x + 1 < room.x + room.width {
+                    open_passage(&mut map, (x, y), (x + 1, y));
+                    sets.union(index((x, y)), index((x + 1, y)));
+                }
+                if y + 1 < room.y + room.height {
+                    open_passage(&mut map, (x, y), (x, y + 1));
+                    sets.union(index((x, y)), index((x, y + 1)));
+                }
+                // The pillars inside the room
+                if x + 1 < room.x + room.width && y + 1 < room.y + room.height {
+                    map.set(x * 2 + 2, y * 2 + 2, Tile::Floor);
+                }
+            }
+        }
+
+        let mut walls: Vec<(Cell, Cell)> = Vec::new();
+        for y in 0..grid.1 {
+            for x in 0..grid.0 {
+                if x + 1 < grid.0 {
+                    walls.push(((x, y), (x + 1, y)));
+                }
+                if y + 1 < grid.1 {
+                    walls.push(((x, y), (x, y + 1)));
+                }
+            }
+        }
+        walls.shuffle(rng);
+        for (a, b) in walls {
+            if sets.union(index(a), index(b)) {
+                open_passage(&mut map, a, b);
+            }
+        }
+
+        if let [first, .., last] = rooms.as_slice() {
+            (map.start_x, map.start_y) = (first.x * 2 + 1, first.y * 2 + 1);
+            map.goal_x = (last.x + last.width - 1) * 2 + 1;
+            map.goal_y = (last.y + last.height - 1) * 2 + 1;
+        }
+
+        map
+    }
+}
+
+/// Tries to place rooms at random, in proportion to the area of the grid,
+/// dropping those near the rooms placed before.
+///
+/// Grids with room for two rooms always get two at least, so that the start
+/// and the goal are in different rooms.
+fn place_rooms(rng: &mut dyn RngCore, grid: (u32, u32)) -> Vec<Room> {
+    let mut rooms: Vec<Room> = Vec::new();
+    let max_width = MAX_ROOM_SIDE.min(grid.0);
+    let max_height = MAX_ROOM_SIDE.min(grid.1);
+    if max_width < MIN_ROOM_SIDE || max_height < MIN_ROOM_SIDE {
+        return rooms;
+    }
+
+    let attempts = (grid.0 * grid.1 / 16).max(4);
+    for _ in 0..attempts {
+        let width = rng.gen_range(MIN_ROOM_SIDE..=max_width);
+        let height = rng.gen_range(MIN_ROOM_SIDE..=max_height);
+        let room = Room {
+            x: rng.gen_range(0..=grid.0 - width),
+            y: rng.gen_range(0..=grid.1 - height),
+            width,
+            height,
+        };
+        if rooms.iter().all(|other| !room.is_near(other)) {
+            rooms.push(room);
+        }
+    }
+
+    // Two of the smallest rooms fit apart along a side of five cells or more
+    let fits_two = grid.0.max(grid.1) > MIN_ROOM_SIDE * 2;
+    while fits_two && rooms.len() < 2 {
+        let free: Vec<Room> = (0..=grid.1 - MIN_ROOM_SIDE)
+            .flat_map(|y| (0..=grid.0 - MIN_ROOM_SIDE).map(move |x| (x, y)))
+            .map(|(x, y)| Room {
+                x,
+                y,
+                width: MIN_ROOM_SIDE,
+                height: MIN_ROOM_SIDE,
+            })
+            .filter(|room| rooms.iter().all(|other| !room.is_near(other)))
+            .collect();
+        match free.choose(rng) {
+            Some(&room) => rooms.push(room),
+            // The room placed leaves no space, so start over from opposite corners
+            None => {
+                let corner = |x, y| Room {
+                    x,
+                    y,
+                    width: MIN_ROOM_SIDE,
+                    height: MIN_ROOM_SIDE,
+                };
+                rooms = vec![
+                    corner(0, 0),
+                    corner(grid.0 - MIN_ROOM_SIDE, grid.1 - MIN_ROOM_SIDE),
+                ];
+            }
+        }
+    }
+    rooms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn generate(seed: u64, width: u32, height: u32) -> Map {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        Rooms.generate(&mut rng, Size::new(width, height).unwrap())
+    }
+
+    #[test]
+    fn places_rooms_apart() {
+        for seed in 0..20 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let rooms = place_rooms(&mut rng, (20, 15));
+            assert!(rooms.len() >= 2);
+            for (i, a) in rooms.iter().enumerate() {
+                assert!(a.x + a.width <= 20 && a.y + a.height <= 15);
+                assert!(rooms[i + 1..].iter().all(|b| !a.is_near(b)));
+            }
+        }
+    }
+
+    #[test]
+    fn places_two_rooms_whenever_they_fit() {
+        for seed in 0..1000 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            // The grid of cells of a 21x21 map
+            assert!(place_rooms(&mut rng, (10, 10)).len() >= 2, "seed {seed}");
+        }
+        for grid in [(5, 2), (2, 5), (5, 5), (6, 3)] {
+            for seed in 0..100 {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                let rooms = place_rooms(&mut rng, grid);
+                assert!(rooms.len() >= 2, "{grid:?}, seed {seed}");
+                assert!(!rooms[0].is_near(&rooms[1]));
+            }
+        }
+        for seed in 0..100 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            assert!(place_rooms(&mut rng, (4, 4)).len() < 2);
+        }
+    }
+
+    #[test]
+    fn joins_rooms_with_corridors() {
+        for seed in 0..20 {
+            let map = generate(seed, 41, 31);
+            assert_eq!(map.validate(), Ok(()));
+            assert_ne!((map.start_x, map.start_y), (map.goal_x, map.goal_y));
+            // Both lie in a room, which is at least three tiles wide
+            let (sx, sy) = (map.start_x as i32, map.start_y as i32);
+            let (gx, gy) = (map.goal_x as i32, map.goal_y as i32);
+            for d in 0..9 {
+                let (dx, dy) = (d % 3, d / 3);
+                assert_eq!(map.get(sx + dx, sy + dy), Some(Tile::Floor));
+                assert_eq!(map.get(gx - dx, gy - dy), Some(Tile::Floor));
+            }
+        }
+        assert_eq!(generate(0, 999, 999).validate(), Ok(()));
+    }
+
+    #[test]
+    fn keeps_corners_without_two_rooms() {
+        let map = generate(0, 5, 5);
+        assert_eq!(map.validate(), Ok(()));
+        assert_eq!((map.start_x, map.start_y), (1, 1));
+        assert_eq!((map.goal_x, map.goal_y), (3, 3));
+        for seed in 0..20 {
+            let map = generate(seed, 9, 9);
+            assert_eq!((map.start_x, map.start_y), (1, 1));
+            assert_eq!((map.goal_x, map.goal_y), (7, 7));
+        }
+    }
+}
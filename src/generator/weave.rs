@@ -0,0 +1,128 @@
+use super::{cell_grid, open_cell, open_passage, Cell, MazeGenerator};
+use crate::map::{Axis, DisjointSet, Map, Size, Tile};
+use rand::{seq::SliceRandom, Rng, RngCore};
+
+/// One in this many cells inside the grid is tried as a crossing.
+const CROSSING_ODDS: u32 = 3;
+
+/// Lays corridors crossing over each other first, then joins the rest in
+/// the style of [`super::Kruskal`], so that the maze stays perfect when the
+/// passage under each bridge counts as a single link.
+///
+/// Crossings are never next to each other nor on the edge of the grid, so
+/// each of them has four arms leading to plain cells.
+pub struct Weave;
+
+impl MazeGenerator for Weave {
+    fn name(&self) -> &'static str {
+        "weave"
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore, size: Size) -> Map {
+        let grid = cell_grid(size);
+        let mut map = Map::filled(size, Tile::Wall);
+        let index = |(x, y): Cell| (x + y * grid.0) as usize;
+        let mut sets = DisjointSet::new((grid.0 * grid.1) as usize);
+        let mut is_crossing = vec![false; (grid.0 * grid.1) as usize];
+
+        let mut inner: Vec<Cell> = (1..grid.1 - 1)
+            .flat_map(|y| (1..grid.0 - 1).map(move |x| (x, y)))
+            .collect();
+        inner.shuffle(rng);
+        for cell in inner {
+            if !rng.gen_ratio(1, CROSSING_ODDS) {
+                continue;
+            }
+            let (x, y) = cell;
+            let west_east = [(x - 1, y), (x + 1, y)];
+            let north_south = [(x, y - 1), (x, y + 1)];
+            let is_taken = [
+                cell,
+                west_east[0],
+                west_east[1],
+                north_south[0],
+                north_south[1],
+            ]
+            .iter()
+            .any(|&c| is_crossing[index(c)]);
+            if is_taken {
+                continue;
+            }
+            let over = if rng.gen() {
+                Axis::Horizontal
+            } else {
+                Axis::Vertical
+            };
+            let (through, under) = match over {
+                Axis::Horizontal => (west_east, north_south),
+                Axis::Vertical => (north_south, west_east),
+            };
+            // Both corridors must join cells not connected yet, even once the other is laid
+            let roots = |pair: [Cell; 2], sets: &mut DisjointSet| {
+                let mut roots = [sets.find(index(pair[0])), sets.find(index(pair[1]))];
+                roots.sort();
+                roots
+            };
+            let (through_roots, under_roots) = (roots(through, &mut sets), roots(under, &mut sets));
+            if through_roots[0] == through_roots[1]
+                || under_roots[0] == under_roots[1]
+                || through_roots == under_roots
+            {
+                continue;
+            }
+
+            is_crossing[index(cell)] = true;
+            for arm in through {
+                sets.union(index(cell), index(arm));
+                open_passage(&mut map, cell, arm);
+            }
+            sets.union(index(under[0]), index(under[1]));
+            for arm in under {
+                open_passage(&mut map, cell, arm);
+            }
+            map.set(x * 2 + 1, y * 2 + 1, Tile::Crossing { over });
+        }
+
+        let mut walls: Vec<(Cell, Cell)> = Vec::new();
+        for y in 0..grid.1 {
+            for x in 0..grid.0 {
+                if is_crossing[index((x, y))] {
+                    continue;
+                }
+                open_cell(&mut map, (x, y));
+                if x + 1 < grid.0 && !is_crossing[index((x + 1, y))] {
+                    walls.push(((x, y), (x + 1, y)));
+                }
+                if y + 1 < grid.1 && !is_crossing[index((x, y + 1))] {
+                    walls.push(((x, y), (x, y + 1)));
+                }
+            }
+        }
+        walls.shuffle(rng);
+        for (a, b) in walls {
+            if sets.union(index(a), index(b)) {
+                open_passage(&mut map, a, b);
+            }
+        }
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn weaves_many_crossings() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let map = Weave.generate(&mut rng, Size::new(201, 201).unwrap());
+        let crossings = (0..201)
+            .flat_map(|y| (0..201).map(move |x| (x, y)))
+            .filter(|&(x, y)| matches!(map.get(x, y), Some(Tile::Crossing { .. })))
+            .count();
+        assert!(crossings > 500, "{crossings}");
+    }
+}
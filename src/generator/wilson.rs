@@ -0,0 +1,85 @@
+use super::{cell_grid, neighbors, open_cell, open_passage, Cell, MazeGenerator};
+use crate::map::{Map, Size, Tile};
+use rand::{seq::IteratorRandom, Rng, RngCore};
+
+/// Adds loop-erased random walks to the maze until it covers every cell,
+/// which picks each perfect maze of the size with the same probability.
+///
+/// Unlike the other generators this has no bias toward any texture, at the
+/// cost of slow starts while the first walks search for the maze.
+pub struct Wilson;
+
+impl MazeGenerator for Wilson {
+    fn name(&self) -> &'static str {
+        "wilson"
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore, size: Size) -> Map {
+        let grid = cell_grid(size);
+        let mut map = Map::filled(size, Tile::Wall);
+        let index = |(x, y): Cell| (x + y * grid.0) as usize;
+        let mut in_maze = vec![false; (grid.0 * grid.1) as usize];
+        // Where the current walk last left each cell to; revisiting a cell
+        // overwrites it, which erases the loop made since the first visit
+        let mut exits: Vec<Option<Cell>> = vec![None; in_maze.len()];
+
+        let first = (rng.gen_range(0..grid.0), rng.gen_range(0..grid.1));
+        in_maze[index(first)] = true;
+        open_cell(&mut map, first);
+
+        for y in 0..grid.1 {
+            for x in 0..grid.0 {
+                let start = (x, y);
+                if in_maze[index(start)] {
+                    continue;
+                }
+
+                let mut cell = start;
+                while !in_maze[index(cell)] {
+                    let next = neighbors(cell, grid)
+                        .choose(rng)
+                        .expect("every cell has a neighbor");
+                    exits[index(cell)] = Some(next);
+                    cell = next;
+                }
+
+                let mut cell = start;
+                while !in_maze[index(cell)] {
+                    in_maze[index(cell)] = true;
+                    let next = exits[index(cell)].expect("the walk left every cell on its path");
+                    open_passage(&mut map, cell, next);
+                    cell = next;
+                }
+            }
+        }
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn picks_mazes_uniformly() {
+        // Two by two cells have four perfect mazes, each missing one passage
+        let mut counts = [0; 4];
+        for seed in 0..4000 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let map = Wilson.generate(&mut rng, Size::new(5, 5).unwrap());
+            let walls = [(2, 1), (1, 2), (3, 2), (2, 3)];
+            let closed = walls
+                .iter()
+                .position(|&(x, y)| map.get(x, y) == Some(Tile::Wall))
+                .unwrap();
+            counts[closed] += 1;
+        }
+        assert!(
+            counts.iter().all(|&c| (900..1100).contains(&c)),
+            "{counts:?}"
+        );
+    }
+}
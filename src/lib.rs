@@ -0,0 +1,17 @@
+//! Platform-independent core of the 3D maze game.
+//!
+//! Everything in this crate builds on any target. The `Random3dMaze` binary
+//! depends on it and runs a Win32/Direct2D frontend on Windows and a
+//! winit frontend presenting software-rendered frames elsewhere, and
+//! `maze3d-tui` plays it in a terminal using [`text`]. The binaries are built
+//! with the `desktop` and `tui` features, on by default, so that the library
+//! alone does not pull in the window and terminal crates.
+
+pub mod bmp;
+pub mod game;
+pub mod generator;
+pub mod map;
+pub mod player;
+pub mod render;
+pub mod text;
+pub mod view;
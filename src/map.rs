@@ -1,81 +1,598 @@
-use rand::Rng;
-
-#[derive(Clone, PartialEq, Eq)]
-pub enum Tile {
-    Floor,
-    Wall,
-}
-
-pub struct Map {
-    pub width: u32,
-    pub height: u32,
-    pub start_x: u32,
-    pub start_y: u32,
-    pub goal_x: u32,
-    pub goal_y: u32,
-    data: Vec<Tile>,
-}
-
-impl Map {
-    pub fn new(rng: &mut impl Rng, width: u32, height: u32) -> Self {
-        let mut data = vec![Tile::Wall; (width * height) as usize];
-
-        for y in 1..(height - 1) {
-            for x in 1..(width - 1) {
-                if x % 2 != 1 && y % 2 != 1 {
-                    continue;
-                }
-                data[(x + y * width) as usize] = Tile::Floor;
-            }
-        }
-
-        for y in (2..(height - 2)).step_by(2) {
-            let x = 2u32;
-            let is_horizontal: bool = rng.gen();
-            if is_horizontal {
-                let new_x: u32 = if rng.gen() { x + 1 } else { x - 1 };
-                data[(new_x + y * width) as usize] = Tile::Wall;
-            } else {
-                let new_y: u32 = if rng.gen() { y + 1 } else { y - 1 };
-                data[(x + new_y * width) as usize] = Tile::Wall;
-            }
-        }
-
-        for x in (4..(width - 2)).step_by(2) {
-            for y in (2..(height - 2)).step_by(2) {
-                let is_horizontal: bool = rng.gen();
-                if is_horizontal {
-                    let new_x: u32 = x + 1;
-                    data[(new_x + y * width) as usize] = Tile::Wall;
-                } else {
-                    let new_y: u32 = if rng.gen() { y + 1 } else { y - 1 };
-                    data[(x + new_y * width) as usize] = Tile::Wall;
-                }
-            }
-        }
-
-        Map {
-            width,
-            height,
-            data,
-            start_x: 1,
-            start_y: 1,
-            goal_x: width - 2,
-            goal_y: height - 2,
-        }
-    }
-
-    fn index_of(&self, x: i32, y: i32) -> Option<usize> {
-        if x < 0 || x as u32 >= self.width {
-            return None;
-        }
-        if y < 0 || y as u32 >= self.height {
-            return None;
-        }
-        Some(((x as u32) + (y as u32) * self.width) as usize)
-    }
-
-    pub fn get(&self, x: i32, y: i32) -> Option<Tile> {
-        self.index_of(x, y).map(|i| self.data[i].clone())
-    }
-}
+mod disjoint_set;
+mod mask;
+mod path;
+mod stats;
+mod validate;
+
+pub use disjoint_set::DisjointSet;
+pub use mask::{Mask, MaskError};
+pub use path::Position;
+pub use stats::MazeStats;
+pub use validate::ValidationError;
+
+use rand::{seq::SliceRandom, Rng, RngCore};
+use std::str::FromStr;
+
+/// The smallest width and height of a maze: one ring of corridors.
+pub const MIN_SIZE: u32 = 5;
+/// The largest width and height of a maze.
+pub const MAX_SIZE: u32 = 999;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum SizeError {
+    #[error("The maze size must be at least {MIN_SIZE}x{MIN_SIZE} ({0}x{1})")]
+    TooSmall(u32, u32),
+    #[error("The maze size must be at most {MAX_SIZE}x{MAX_SIZE} ({0}x{1})")]
+    TooLarge(u32, u32),
+    #[error("The maze width and height must be odd ({0}x{1})")]
+    Even(u32, u32),
+    #[error("Cannot read a maze size from \"{0}\", expected WIDTHxHEIGHT")]
+    Malformed(String),
+}
+
+/// The validated width and height of a map, in tiles including the outer walls.
+///
+/// Both are odd so that corridors on odd coordinates are surrounded by walls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Size {
+    width: u32,
+    height: u32,
+}
+
+impl Size {
+    pub fn new(width: u32, height: u32) -> Result<Self, SizeError> {
+        let size = Self::unbounded(width, height)?;
+        if width > MAX_SIZE || height > MAX_SIZE {
+            return Err(SizeError::TooLarge(width, height));
+        }
+        Ok(size)
+    }
+
+    /// Like [`Size::new`] but beyond `MAX_SIZE`, for mazes that are never
+    /// held in a [`Map`] as a whole.
+    pub fn unbounded(width: u32, height: u32) -> Result<Self, SizeError> {
+        if width < MIN_SIZE || height < MIN_SIZE {
+            return Err(SizeError::TooSmall(width, height));
+        }
+        if width.is_multiple_of(2) || height.is_multiple_of(2) {
+            return Err(SizeError::Even(width, height));
+        }
+        Ok(Size { width, height })
+    }
+
+    /// Reads `WIDTHxHEIGHT` like [`Size::from_str`], but beyond `MAX_SIZE`,
+    /// see [`Size::unbounded`].
+    pub fn parse_unbounded(s: &str) -> Result<Self, SizeError> {
+        let (width, height) = Self::parse_dimensions(s)?;
+        Size::unbounded(width, height)
+    }
+
+    fn parse_dimensions(s: &str) -> Result<(u32, u32), SizeError> {
+        let malformed = || SizeError::Malformed(s.to_owned());
+        let (width, height) = s.split_once(['x', 'X']).ok_or_else(malformed)?;
+        let width = width.trim().parse().map_err(|_| malformed())?;
+        let height = height.trim().parse().map_err(|_| malformed())?;
+        Ok((width, height))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl Default for Size {
+    fn default() -> Self {
+        Size {
+            width: 21,
+            height: 21,
+        }
+    }
+}
+
+/// Parses `WIDTHxHEIGHT`, e.g. `31x21`.
+impl FromStr for Size {
+    type Err = SizeError;
+
+    fn from_str(s: &str) -> Result<Self, SizeError> {
+        let (width, height) = Self::parse_dimensions(s)?;
+        Size::new(width, height)
+    }
+}
+
+/// The two ways a corridor can run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Axis {
+    /// From west to east
+    Horizontal,
+    /// From north to south
+    Vertical,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Tile {
+    Floor,
+    Wall,
+    /// Leads to the same place on the floor above, see [`Map::stacked`].
+    StairsUp,
+    /// Leads to the same place on the floor below.
+    StairsDown,
+    /// A cell where two corridors cross without joining, the one along
+    /// `over` on a bridge above the other.
+    Crossing {
+        over: Axis,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Map {
+    pub width: u32,
+    pub height: u32,
+    pub start_x: u32,
+    pub start_y: u32,
+    pub goal_x: u32,
+    pub goal_y: u32,
+    /// The number of stacked floors, the first one at the top.
+    pub floors: u32,
+    /// The floor of the goal, while the start is always on the first one.
+    pub goal_floor: u32,
+    /// Whether leaving the map on one edge enters it on the opposite one,
+    /// see [`Map::wrap`].
+    pub wraps: bool,
+    data: Vec<Tile>,
+}
+
+impl Map {
+    /// A map of `size` covered with `tile`, going from the top left corner
+    /// to the bottom right one.
+    pub fn filled(size: Size, tile: Tile) -> Self {
+        let (width, height) = (size.width, size.height);
+        Map {
+            width,
+            height,
+            start_x: 1,
+            start_y: 1,
+            goal_x: width - 2,
+            goal_y: height - 2,
+            floors: 1,
+            goal_floor: 0,
+            wraps: false,
+            data: vec![tile; (width * height) as usize],
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        Size {
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// The tile at `(x, y)`, taken modulo the size on wrapping maps, or
+    /// `None` outside of the others.
+    pub fn position(&self, x: i32, y: i32) -> Option<(u32, u32)> {
+        if self.wraps {
+            return Some((
+                x.rem_euclid(self.width as i32) as u32,
+                y.rem_euclid(self.height as i32) as u32,
+            ));
+        }
+        if x < 0 || x as u32 >= self.width {
+            return None;
+        }
+        if y < 0 || y as u32 >= self.height {
+            return None;
+        }
+        Some((x as u32, y as u32))
+    }
+
+    fn index_of(&self, floor: u32, x: i32, y: i32) -> Option<usize> {
+        if floor >= self.floors {
+            return None;
+        }
+        self.position(x, y)
+            .map(|(x, y)| (x + (y + floor * self.height) * self.width) as usize)
+    }
+
+    /// The tile at `(x, y)` on the first floor.
+    pub fn get(&self, x: i32, y: i32) -> Option<Tile> {
+        self.get_on(0, x, y)
+    }
+
+    pub fn get_on(&self, floor: u32, x: i32, y: i32) -> Option<Tile> {
+        self.index_of(floor, x, y).map(|i| self.data[i].clone())
+    }
+
+    /// Sets the tile at `(x, y)` on the first floor. Panics if `(x, y)` is
+    /// outside of the map.
+    pub fn set(&mut self, x: u32, y: u32, tile: Tile) {
+        self.set_on(0, x, y, tile);
+    }
+
+    /// Panics if `(x, y)` or `floor` is outside of the map.
+    pub fn set_on(&mut self, floor: u32, x: u32, y: u32, tile: Tile) {
+        assert!(
+            x < self.width && y < self.height && floor < self.floors,
+            "({x}, {y}) on floor {floor} is outside of the map"
+        );
+        self.data[(x + (y + floor * self.height) * self.width) as usize] = tile;
+    }
+
+    /// Stacks maps of the same size from the top floor down, joining each
+    /// floor to the next with stairs, placed at random on a tile that is
+    /// floor on both. The start stays on the top floor and the goal is the
+    /// one of the bottom floor, so the stairs may stand where the start or
+    /// the goal of the floors in between were.
+    ///
+    /// Panics if `floors` is empty, if their sizes differ, or if two floors
+    /// leave no room for stairs besides the start and the goal.
+    pub fn stacked(rng: &mut dyn RngCore, floors: Vec<Map>) -> Map {
+        let mut floors = floors.into_iter();
+        let mut map = floors.next().expect("a map has one floor at least");
+        for next in floors {
+            assert_eq!(map.size(), next.size());
+            let upper = map.floors - 1;
+            map.floors += 1;
+            map.goal_floor = upper + 1;
+            (map.goal_x, map.goal_y) = (next.goal_x, next.goal_y);
+            map.data.extend(next.data);
+
+            let start = (map.start_x as i32, map.start_y as i32);
+            let goal = (map.goal_x as i32, map.goal_y as i32);
+            let candidates: Vec<(i32, i32)> = (0..map.height as i32)
+                .flat_map(|y| (0..map.width as i32).map(move |x| (x, y)))
+                .filter(|&p| (p != start || upper > 0) && p != goal)
+                .filter(|&(x, y)| {
+                    map.get_on(upper, x, y) == Some(Tile::Floor)
+                        && map.get_on(upper + 1, x, y) == Some(Tile::Floor)
+                })
+                .collect();
+            let &(x, y) = candidates.choose(rng).expect("no room for stairs");
+            map.set_on(upper, x as u32, y as u32, Tile::StairsDown);
+            map.set_on(upper + 1, x as u32, y as u32, Tile::StairsUp);
+        }
+        map
+    }
+
+    /// Whether `(x, y)` on the first floor can be walked on.
+    fn is_open(&self, x: i32, y: i32) -> bool {
+        self.get(x, y).is_some_and(|tile| tile != Tile::Wall)
+    }
+
+    fn is_dead_end(&self, x: i32, y: i32) -> bool {
+        self.is_open(x, y)
+            && [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                .into_iter()
+                .filter(|&(x, y)| self.is_open(x, y))
+                .count()
+                == 1
+    }
+
+    /// The floor tiles with a single way out, from the top row to the bottom
+    /// one, on the first floor only.
+    pub fn dead_ends(&self) -> Vec<(u32, u32)> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.is_dead_end(x as i32, y as i32))
+            .collect()
+    }
+
+    /// Makes the edges wrap around like on a torus, and opens passages
+    /// across the seams.
+    ///
+    /// A seam passage opens the two border tiles facing each other between
+    /// floor tiles, so it is two tiles long. About one row or column of cells
+    /// in four gets one, and each seam gets one at least.
+    ///
+    /// Maps are wrapped one floor at a time before they are stacked, see
+    /// [`Map::stacked`], so this panics if the map has several floors.
+    pub fn wrap(&mut self, rng: &mut dyn RngCore) {
+        assert_eq!(self.floors, 1, "only single floors can be wrapped");
+        self.wraps = true;
+        let (last_x, last_y) = (self.width - 1, self.height - 1);
+        let rows: Vec<u32> = (1..last_y)
+            .step_by(2)
+            .filter(|&y| self.is_open(1, y as i32) && self.is_open(last_x as i32 - 1, y as i32))
+            .collect();
+        for y in pick_seams(rng, &rows) {
+            self.set(0, y, Tile::Floor);
+            self.set(last_x, y, Tile::Floor);
+        }
+        let columns: Vec<u32> = (1..last_x)
+            .step_by(2)
+            .filter(|&x| self.is_open(x as i32, 1) && self.is_open(x as i32, last_y as i32 - 1))
+            .collect();
+        for x in pick_seams(rng, &columns) {
+            self.set(x, 0, Tile::Floor);
+            self.set(x, last_y, Tile::Floor);
+        }
+    }
+
+    /// Removes `percent` percent of the dead ends, picked at random, by
+    /// opening the wall in front of each onto the floor beyond it, which
+    /// makes loops.
+    ///
+    /// Walls opening onto another dead end are preferred, so that one
+    /// opening removes two dead ends. The outer walls are never opened.
+    ///
+    /// Maps are braided one floor at a time before they are stacked, see
+    /// [`Map::stacked`], so this panics if the map has several floors.
+    pub fn braid(&mut self, rng: &mut dyn RngCore, percent: u32) {
+        assert_eq!(self.floors, 1, "only single floors can be braided");
+        let mut dead_ends = self.dead_ends();
+        dead_ends.shuffle(rng);
+        let count = dead_ends.len() * percent.min(100) as usize / 100;
+
+        for &(x, y) in &dead_ends[..count] {
+            let (x, y) = (x as i32, y as i32);
+            // An earlier opening may have reached it already
+            if !self.is_dead_end(x, y) {
+                continue;
+            }
+            let openings: Vec<((i32, i32), (i32, i32))> = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                .into_iter()
+                .map(|(dx, dy)| ((x + dx, y + dy), (x + dx * 2, y + dy * 2)))
+                .filter(|&((wall_x, wall_y), (beyond_x, beyond_y))| {
+                    let is_inner = wall_x > 0
+                        && wall_y > 0
+                        && wall_x < self.width as i32 - 1
+                        && wall_y < self.height as i32 - 1;
+                    is_inner && !self.is_open(wall_x, wall_y) && self.is_open(beyond_x, beyond_y)
+                })
+                .collect();
+            let onto_dead_ends: Vec<_> = openings
+                .iter()
+                .filter(|(_, (beyond_x, beyond_y))| self.is_dead_end(*beyond_x, *beyond_y))
+                .copied()
+                .collect();
+            let choices = if onto_dead_ends.is_empty() {
+                &openings
+            } else {
+                &onto_dead_ends
+            };
+            if let Some(&((wall_x, wall_y), _)) = choices.choose(rng) {
+                self.set(wall_x as u32, wall_y as u32, Tile::Floor);
+            }
+        }
+    }
+}
+
+/// Picks one in four of `candidates` at random, and one at least unless
+/// there are none.
+fn pick_seams(rng: &mut dyn RngCore, candidates: &[u32]) -> Vec<u32> {
+    let mut picked: Vec<u32> = candidates
+        .iter()
+        .copied()
+        .filter(|_| rng.gen_ratio(1, 4))
+        .collect();
+    if picked.is_empty() {
+        picked.extend(candidates.choose(rng));
+    }
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{Backtracker, BarKnocking, MazeGenerator};
+    use rand::SeedableRng;
+
+    fn generate(seed: u64, width: u32, height: u32) -> Map {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+        BarKnocking.generate(&mut rng, Size::new(width, height).unwrap())
+    }
+
+    #[test]
+    fn border_is_wall() {
+        let map = generate(0, 21, 21);
+        for x in 0..21 {
+            assert_eq!(map.get(x, 0), Some(Tile::Wall));
+            assert_eq!(map.get(x, 20), Some(Tile::Wall));
+        }
+        for y in 0..21 {
+            assert_eq!(map.get(0, y), Some(Tile::Wall));
+            assert_eq!(map.get(20, y), Some(Tile::Wall));
+        }
+    }
+
+    #[test]
+    fn start_and_goal_are_floor() {
+        let map = generate(0, 21, 21);
+        assert_eq!((map.start_x, map.start_y), (1, 1));
+        assert_eq!((map.goal_x, map.goal_y), (19, 19));
+        assert_eq!(map.get(1, 1), Some(Tile::Floor));
+        assert_eq!(map.get(19, 19), Some(Tile::Floor));
+    }
+
+    #[test]
+    fn out_of_range_is_none() {
+        let map = generate(0, 21, 21);
+        assert_eq!(map.get(-1, 0), None);
+        assert_eq!(map.get(0, -1), None);
+        assert_eq!(map.get(21, 0), None);
+        assert_eq!(map.get(0, 21), None);
+    }
+
+    #[test]
+    fn same_seed_same_map() {
+        let a = generate(42, 21, 21);
+        let b = generate(42, 21, 21);
+        assert!(a.data == b.data);
+    }
+
+    #[test]
+    fn rejects_invalid_sizes() {
+        assert_eq!(Size::new(3, 21), Err(SizeError::TooSmall(3, 21)));
+        assert_eq!(Size::new(21, 0), Err(SizeError::TooSmall(21, 0)));
+        assert_eq!(Size::new(1001, 21), Err(SizeError::TooLarge(1001, 21)));
+        assert_eq!(Size::new(21, 20), Err(SizeError::Even(21, 20)));
+    }
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!("31x21".parse(), Size::new(31, 21));
+        assert_eq!(" 5 X 7 ".parse(), Size::new(5, 7));
+        assert!(matches!("31".parse::<Size>(), Err(SizeError::Malformed(_))));
+        assert!(matches!(
+            "31x".parse::<Size>(),
+            Err(SizeError::Malformed(_))
+        ));
+        assert_eq!("8x8".parse::<Size>(), Err(SizeError::Even(8, 8)));
+
+        assert_eq!(
+            Size::parse_unbounded("100001x5"),
+            Size::unbounded(100_001, 5)
+        );
+        assert_eq!(
+            "100001x5".parse::<Size>(),
+            Err(SizeError::TooLarge(100_001, 5))
+        );
+        assert!(matches!(
+            Size::parse_unbounded("5 by 5"),
+            Err(SizeError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn every_size_keeps_start_and_goal_open() {
+        for (width, height) in [(5, 5), (5, 31), (41, 7), (301, 201), (999, 999)] {
+            let map = generate(3, width, height);
+            assert_eq!(map.data.len(), (width * height) as usize);
+            assert_eq!(map.get(1, 1), Some(Tile::Floor));
+            let (gx, gy) = (map.goal_x as i32, map.goal_y as i32);
+            assert_eq!(map.get(gx, gy), Some(Tile::Floor));
+            assert_eq!(
+                map.get(width as i32 - 1, height as i32 - 1),
+                Some(Tile::Wall)
+            );
+        }
+    }
+
+    #[test]
+    fn braiding_removes_dead_ends() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+        let maze = Backtracker.generate(&mut rng, Size::new(41, 31).unwrap());
+        let count = maze.dead_ends().len();
+        assert!(count > 10);
+
+        let mut unchanged = maze.clone();
+        unchanged.braid(&mut rng, 0);
+        assert_eq!(unchanged, maze);
+
+        let mut half = maze.clone();
+        half.braid(&mut rng, 50);
+        let half_count = half.dead_ends().len();
+        assert!(
+            half_count < count && half_count > 0,
+            "{half_count} of {count}"
+        );
+
+        let mut braided = maze.clone();
+        braided.braid(&mut rng, 100);
+        assert!(braided.dead_ends().is_empty());
+        for x in 0..41 {
+            assert_eq!(braided.get(x, 0), Some(Tile::Wall));
+            assert_eq!(braided.get(x, 30), Some(Tile::Wall));
+        }
+        for y in 0..31 {
+            assert_eq!(braided.get(0, y), Some(Tile::Wall));
+            assert_eq!(braided.get(40, y), Some(Tile::Wall));
+        }
+        // Only walls were opened
+        for y in 0..31 {
+            for x in 0..41 {
+                if maze.get(x, y) == Some(Tile::Floor) {
+                    assert_eq!(braided.get(x, y), Some(Tile::Floor));
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "only single floors can be braided")]
+    fn braiding_refuses_stacked_maps() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+        let size = Size::new(21, 15).unwrap();
+        let floors = vec![Backtracker.generate(&mut rng, size); 2];
+        Map::stacked(&mut rng, floors).braid(&mut rng, 100);
+    }
+
+    #[test]
+    fn wrapping_opens_the_seams() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+        let mut map = Backtracker.generate(&mut rng, Size::new(41, 31).unwrap());
+        assert_eq!(map.position(-1, 0), None);
+        map.wrap(&mut rng);
+        assert_eq!(map.position(-1, 31), Some((40, 0)));
+        assert_eq!(map.get(41, -2), map.get(0, 29));
+
+        let rows: Vec<i32> = (0..31).filter(|&y| map.is_open(0, y)).collect();
+        let columns: Vec<i32> = (0..41).filter(|&x| map.is_open(x, 0)).collect();
+        assert!(!rows.is_empty() && !columns.is_empty());
+        for y in rows {
+            assert_eq!(y % 2, 1);
+            assert!(map.is_open(-1, y) && map.is_open(1, y) && map.is_open(-2, y));
+        }
+        for x in columns {
+            assert_eq!(x % 2, 1);
+            assert!(map.is_open(x, -1) && map.is_open(x, 1) && map.is_open(x, -2));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "only single floors can be wrapped")]
+    fn wrapping_refuses_stacked_maps() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+        let size = Size::new(21, 15).unwrap();
+        let floors = vec![Backtracker.generate(&mut rng, size); 2];
+        Map::stacked(&mut rng, floors).wrap(&mut rng);
+    }
+
+    /// The places of `tile` on a floor, from the top row to the bottom one.
+    fn find(map: &Map, floor: u32, tile: Tile) -> Vec<(i32, i32)> {
+        (0..map.height as i32)
+            .flat_map(|y| (0..map.width as i32).map(move |x| (x, y)))
+            .filter(|&(x, y)| map.get_on(floor, x, y) == Some(tile.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn stacks_floors_with_stairs() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+        let size = Size::new(21, 15).unwrap();
+        let floors: Vec<Map> = (0..3)
+            .map(|_| Backtracker.generate(&mut rng, size))
+            .collect();
+        let map = Map::stacked(&mut rng, floors.clone());
+        assert_eq!((map.floors, map.goal_floor), (3, 2));
+        assert_eq!(map.get_on(3, 1, 1), None);
+
+        for floor in 0..2 {
+            let down = find(&map, floor, Tile::StairsDown);
+            assert_eq!(down.len(), 1);
+            assert_eq!(find(&map, floor + 1, Tile::StairsUp), down);
+        }
+        assert!(find(&map, 0, Tile::StairsUp).is_empty());
+        assert!(find(&map, 2, Tile::StairsDown).is_empty());
+        // The walls are left as generated
+        for (floor, original) in floors.iter().enumerate() {
+            assert_eq!(
+                find(&map, floor as u32, Tile::Wall),
+                find(original, 0, Tile::Wall)
+            );
+        }
+
+        // Three tiles only leave room for stairs where a start was
+        let mut corridor = Map::filled(Size::new(5, 5).unwrap(), Tile::Wall);
+        for x in 1..4 {
+            corridor.set(x, 1, Tile::Floor);
+        }
+        (corridor.goal_x, corridor.goal_y) = (3, 1);
+        let map = Map::stacked(&mut rng, vec![corridor; 4]);
+        assert_eq!(map.validate(), Ok(()));
+        assert_eq!(find(&map, 1, Tile::StairsDown), [(1, 1)]);
+    }
+}
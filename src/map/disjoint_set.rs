@@ -0,0 +1,81 @@
+/// Sets of indices `0..len` that can be merged, with near constant time
+/// operations (union by size and path halving).
+#[derive(Clone, Debug)]
+pub struct DisjointSet {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl DisjointSet {
+    /// Puts each index in a set of its own.
+    pub fn new(len: usize) -> Self {
+        DisjointSet {
+            parents: (0..len).collect(),
+            sizes: vec![1; len],
+        }
+    }
+
+    /// The representative of the set containing `i`.
+    pub fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    /// Merges the sets containing `a` and `b`, returning false if they were
+    /// already the same.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.sizes[a] < self.sizes[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parents[b] = a;
+        self.sizes[a] += self.sizes[b];
+        true
+    }
+
+    pub fn is_same(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// The number of indices in the set containing `i`.
+    pub fn size_of(&mut self, i: usize) -> usize {
+        let root = self.find(i);
+        self.sizes[root]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_sets() {
+        let mut sets = DisjointSet::new(5);
+        assert!(!sets.is_same(0, 1));
+        assert!(sets.union(0, 1));
+        assert!(sets.union(3, 4));
+        assert!(!sets.union(1, 0));
+        assert!(sets.is_same(1, 0));
+        assert!(!sets.is_same(1, 3));
+        assert!(sets.union(4, 1));
+        assert!(sets.is_same(0, 3));
+        assert_eq!(sets.size_of(3), 4);
+        assert_eq!(sets.size_of(2), 1);
+    }
+
+    #[test]
+    fn handles_long_chains() {
+        let mut sets = DisjointSet::new(100_000);
+        for i in 1..100_000 {
+            sets.union(i - 1, i);
+        }
+        assert_eq!(sets.size_of(0), 100_000);
+        assert!(sets.is_same(0, 99_999));
+    }
+}
@@ -0,0 +1,386 @@
+use super::{Axis, DisjointSet, Map, Size, SizeError, Tile};
+use crate::bmp::{BmpError, Image};
+use rand::{seq::SliceRandom, RngCore};
+use std::path::Path;
+
+#[derive(thiserror::Error, Debug)]
+pub enum MaskError {
+    #[error("Cannot read a mask from file ({file_path}): {source}")]
+    Read {
+        file_path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Image(#[from] BmpError),
+    #[error("The mask does not fit a maze: {0}")]
+    Size(#[from] SizeError),
+    #[error("A mask of {0}x{1} cells cannot hold {2} cells")]
+    CellCount(u32, u32, usize),
+    #[error("A mask needs two cells at least")]
+    TooFewCells,
+    #[error("The cells of a mask must all be connected to each other")]
+    Disconnected,
+}
+
+/// Which cells of a maze may be opened, for mazes in the shape of a picture.
+///
+/// Cell `(x, y)` of the mask is the tile `(2x + 1, 2y + 1)` of the map, so
+/// a mask of `w` by `h` cells makes a map of `2w + 1` by `2h + 1` tiles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mask {
+    name: String,
+    width: u32,
+    height: u32,
+    cells: Vec<bool>,
+}
+
+impl Mask {
+    pub fn new(name: &str, width: u32, height: u32, cells: Vec<bool>) -> Result<Self, MaskError> {
+        if cells.len() as u64 != width as u64 * height as u64 {
+            return Err(MaskError::CellCount(width, height, cells.len()));
+        }
+        // Saturating so that huge masks are too large rather than overflowing
+        let tiles = |cells: u32| cells.saturating_mul(2).saturating_add(1);
+        Size::new(tiles(width), tiles(height))?;
+        let mask = Mask {
+            name: name.to_owned(),
+            width,
+            height,
+            cells,
+        };
+
+        let inside: Vec<usize> = (0..mask.cells.len()).filter(|&i| mask.cells[i]).collect();
+        if inside.len() < 2 {
+            return Err(MaskError::TooFewCells);
+        }
+        let mut seen = vec![false; mask.cells.len()];
+        let mut stack = vec![inside[0]];
+        seen[inside[0]] = true;
+        let mut reached = 0;
+        while let Some(i) = stack.pop() {
+            reached += 1;
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            for (nx, ny) in [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ] {
+                if mask.contains(nx, ny) && !seen[(nx + ny * width) as usize] {
+                    seen[(nx + ny * width) as usize] = true;
+                    stack.push((nx + ny * width) as usize);
+                }
+            }
+        }
+        if reached != inside.len() {
+            return Err(MaskError::Disconnected);
+        }
+
+        Ok(mask)
+    }
+
+    /// Reads ASCII art with one character per cell, where spaces and dots
+    /// are outside of the mask and anything else inside.
+    pub fn from_ascii(name: &str, text: &str) -> Result<Self, MaskError> {
+        let lines: Vec<&str> = text.lines().collect();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as u32;
+        let height = lines.len() as u32;
+        let mut cells = vec![false; (width * height) as usize];
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                cells[x + y * width as usize] = !matches!(c, ' ' | '.');
+            }
+        }
+        Self::new(name, width, height, cells)
+    }
+
+    /// Reads an image with one pixel per cell, where dark pixels are inside
+    /// of the mask, e.g. black on a 1-bit image.
+    pub fn from_image(name: &str, image: &Image) -> Result<Self, MaskError> {
+        let (width, height) = (image.width(), image.height());
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let color = image.get_pixel(x, y);
+                (color.r as u32 + color.g as u32 + color.b as u32) < 384
+            })
+            .collect();
+        Self::new(name, width, height, cells)
+    }
+
+    /// Reads a BMP image if the file has the `.bmp` extension and ASCII art
+    /// otherwise, named after the file.
+    pub fn load(file_path: &str) -> Result<Self, MaskError> {
+        let read_error = |source| MaskError::Read {
+            file_path: file_path.to_owned(),
+            source,
+        };
+        let path = Path::new(file_path);
+        let name = path
+            .file_stem()
+            .map_or(file_path.into(), |s| s.to_string_lossy());
+        let is_bmp = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("bmp"));
+        if is_bmp {
+            let data = std::fs::read(path).map_err(read_error)?;
+            Self::from_image(&name, &Image::from_bmp(&data)?)
+        } else {
+            let text = std::fs::read_to_string(path).map_err(read_error)?;
+            Self::from_ascii(&name, &text)
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The size of the maps the mask applies to.
+    pub fn size(&self) -> Size {
+        Size {
+            width: self.width * 2 + 1,
+            height: self.height * 2 + 1,
+        }
+    }
+
+    /// Whether cell `(x, y)` is inside of the mask, false outside of its bounds.
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height && self.cells[(x + y * self.width) as usize]
+    }
+
+    /// Whether every cell next to tile `(x, y)` is inside of the mask.
+    fn covers_tile(&self, x: u32, y: u32) -> bool {
+        // Tiles on odd coordinates are cells, the others lie between two or four
+        let cells_on = |t: u32| {
+            if t % 2 == 1 {
+                [Some((t - 1) / 2), None]
+            } else {
+                [(t / 2).checked_sub(1), Some(t / 2)]
+            }
+        };
+        let (xs, ys) = (cells_on(x), cells_on(y));
+        if xs[0].is_none() || ys[0].is_none() {
+            return false;
+        }
+        xs.iter()
+            .flatten()
+            .all(|&cx| ys.iter().flatten().all(|&cy| self.contains(cx, cy)))
+    }
+
+    /// The axis over the bridge of the crossing at cell `(x, y)` of `map`, if
+    /// all four of its arms are inside of the mask.
+    fn bridge_at(&self, map: &Map, x: u32, y: u32) -> Option<Axis> {
+        let arms = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        match map.get(x as i32 * 2 + 1, y as i32 * 2 + 1) {
+            Some(Tile::Crossing { over }) if arms.iter().all(|&(x, y)| self.contains(x, y)) => {
+                Some(over)
+            }
+            _ => None,
+        }
+    }
+
+    /// Walls off the tiles of `map` outside of the mask, then opens walls
+    /// between the cells left inside until they are all connected again,
+    /// and moves the start and the goal inside if needed. Crossings cut by
+    /// the mask become plain cells on the corridor over them.
+    ///
+    /// A perfect maze stays perfect. Panics if the map is not of the size of
+    /// the mask.
+    pub fn apply(&self, map: &mut Map, rng: &mut dyn RngCore) {
+        assert_eq!(map.size(), self.size());
+        for y in 0..map.height {
+            for x in 0..map.width {
+                if !self.covers_tile(x, y) {
+                    map.set(x, y, Tile::Wall);
+                }
+            }
+        }
+
+        // Crossings losing an arm become plain cells, cut off from the corridor under them
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (tile_x, tile_y) = (x * 2 + 1, y * 2 + 1);
+                let Some(Tile::Crossing { over }) = map.get(tile_x as i32, tile_y as i32) else {
+                    continue;
+                };
+                if self.bridge_at(map, x, y).is_some() {
+                    continue;
+                }
+                let under = match over {
+                    Axis::Horizontal => [(tile_x, tile_y - 1), (tile_x, tile_y + 1)],
+                    Axis::Vertical => [(tile_x - 1, tile_y), (tile_x + 1, tile_y)],
+                };
+                for (under_x, under_y) in under {
+                    map.set(under_x, under_y, Tile::Wall);
+                }
+                map.set(tile_x, tile_y, Tile::Floor);
+            }
+        }
+
+        let index = |x: u32, y: u32| (x + y * self.width) as usize;
+        let mut sets = DisjointSet::new(self.cells.len());
+        let mut walls = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !self.contains(x, y) {
+                    continue;
+                }
+                if let Some(over) = self.bridge_at(map, x, y) {
+                    let west_east = [(x - 1, y), (x + 1, y)];
+                    let north_south = [(x, y - 1), (x, y + 1)];
+                    let (through, under) = match over {
+                        Axis::Horizontal => (west_east, north_south),
+                        Axis::Vertical => (north_south, west_east),
+                    };
+                    for (ax, ay) in through {
+                        sets.union(index(x, y), index(ax, ay));
+                    }
+                    sets.union(index(under[0].0, under[0].1), index(under[1].0, under[1].1));
+                    continue;
+                }
+                map.set(x * 2 + 1, y * 2 + 1, Tile::Floor);
+                for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+                    if !self.contains(nx, ny) || self.bridge_at(map, nx, ny).is_some() {
+                        continue;
+                    }
+                    let between = (x + nx + 1, y + ny + 1);
+                    if map.get(between.0 as i32, between.1 as i32) == Some(Tile::Floor) {
+                        sets.union(index(x, y), index(nx, ny));
+                    } else {
+                        walls.push(((x, y), (nx, ny), between));
+                    }
+                }
+            }
+        }
+        walls.shuffle(rng);
+        for ((x, y), (nx, ny), (wall_x, wall_y)) in walls {
+            if sets.union(index(x, y), index(nx, ny)) {
+                map.set(wall_x, wall_y, Tile::Floor);
+            }
+        }
+
+        let mut inside = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.contains(x, y));
+        let first = inside.next().expect("a mask has two cells at least");
+        let last = inside.next_back().expect("a mask has two cells at least");
+        if !self.covers_tile(map.start_x, map.start_y) {
+            (map.start_x, map.start_y) = (first.0 * 2 + 1, first.1 * 2 + 1);
+        }
+        if !self.covers_tile(map.goal_x, map.goal_y) {
+            (map.goal_x, map.goal_y) = (last.0 * 2 + 1, last.1 * 2 + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{Backtracker, MazeGenerator, Rooms, Weave};
+    use crate::render::color_rgb;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    const HEART: &str = "\
+.XXX...XXX.
+XXXXX.XXXXX
+XXXXXXXXXXX
+.XXXXXXXXX.
+..XXXXXXX..
+...XXXXX...
+....XXX....
+.....X.....";
+
+    #[test]
+    fn reads_ascii_art() {
+        let mask = Mask::from_ascii("heart", HEART).unwrap();
+        assert_eq!(mask.size(), Size::new(23, 17).unwrap());
+        assert!(!mask.contains(0, 0));
+        assert!(mask.contains(1, 0));
+        assert!(mask.contains(5, 7));
+        assert!(!mask.contains(11, 0));
+    }
+
+    #[test]
+    fn reads_images() {
+        let mut image = Image::new(3, 2, color_rgb(255, 255, 255));
+        image.set_pixel(0, 0, color_rgb(0, 0, 0));
+        image.set_pixel(1, 0, color_rgb(0, 0, 0));
+        image.set_pixel(1, 1, color_rgb(40, 40, 40));
+        let image = Image::from_bmp(&image.to_bmp()).unwrap();
+        let mask = Mask::from_image("l", &image).unwrap();
+        assert_eq!(mask.size(), Size::new(7, 5).unwrap());
+        assert_eq!(mask.cells, [true, true, false, false, true, false]);
+    }
+
+    #[test]
+    fn rejects_unusable_masks() {
+        assert!(matches!(
+            Mask::from_ascii("islands", "X.X\n..."),
+            Err(MaskError::Disconnected)
+        ));
+        assert!(matches!(
+            Mask::from_ascii("dot", "X.\n.."),
+            Err(MaskError::TooFewCells)
+        ));
+        assert!(matches!(
+            Mask::new("short", 3, 2, vec![true; 5]),
+            Err(MaskError::CellCount(3, 2, 5))
+        ));
+        assert!(matches!(
+            Mask::new("huge", u32::MAX, 1, vec![true; 3]),
+            Err(MaskError::CellCount(_, 1, 3))
+        ));
+        assert!(matches!(
+            Mask::from_ascii("line", "XXXX"),
+            Err(MaskError::Size(SizeError::TooSmall(9, 3)))
+        ));
+    }
+
+    #[test]
+    fn keeps_mazes_inside() {
+        let mask = Mask::from_ascii("heart", HEART).unwrap();
+        for seed in 0..20 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut map = Backtracker.generate(&mut rng, mask.size());
+            mask.apply(&mut map, &mut rng);
+            assert_eq!(map.validate_perfect(), Ok(()));
+            for y in 0..map.height {
+                for x in 0..map.width {
+                    if !mask.covers_tile(x, y) {
+                        assert_eq!(map.get(x as i32, y as i32), Some(Tile::Wall));
+                    }
+                }
+            }
+            // The corners are outside of the heart
+            assert_eq!((map.start_x, map.start_y), (3, 1));
+            assert_eq!((map.goal_x, map.goal_y), (11, 15));
+        }
+    }
+
+    #[test]
+    fn reconnects_any_generator() {
+        let mask = Mask::from_ascii("heart", HEART).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut map = Rooms.generate(&mut rng, mask.size());
+        mask.apply(&mut map, &mut rng);
+        assert_eq!(map.validate(), Ok(()));
+    }
+
+    #[test]
+    fn keeps_crossings_whole() {
+        let mask = Mask::from_ascii("heart", HEART).unwrap();
+        for seed in 0..20 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut map = Weave.generate(&mut rng, mask.size());
+            mask.apply(&mut map, &mut rng);
+            assert_eq!(map.validate_perfect(), Ok(()));
+        }
+    }
+}
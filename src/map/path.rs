@@ -0,0 +1,376 @@
+use super::{Axis, Map, Tile};
+use crate::player::{Direction, Player};
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// A tile of a map, on one of its floors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub x: u32,
+    pub y: u32,
+    pub floor: u32,
+}
+
+/// Where a player stands as far as moving on is concerned.
+type State = (Position, Option<Axis>);
+
+impl Map {
+    pub fn start(&self) -> Position {
+        Position {
+            x: self.start_x,
+            y: self.start_y,
+            floor: 0,
+        }
+    }
+
+    pub fn goal(&self) -> Position {
+        Position {
+            x: self.goal_x,
+            y: self.goal_y,
+            floor: self.goal_floor,
+        }
+    }
+
+    /// The positions walked through on a shortest way from `from` to the
+    /// goal, both included, or `None` if the goal cannot be reached.
+    ///
+    /// Moves follow the rules of [`Player::move_forward`], so paths wrap
+    /// around the edges, take the stairs and keep to the corridor followed
+    /// through crossings.
+    pub fn shortest_path(&self, from: Position) -> Option<Vec<Position>> {
+        let goal = self.goal();
+        let first: State = (from, None);
+        let mut previous: HashMap<State, State> = HashMap::new();
+        let mut queue = VecDeque::from([first]);
+        while let Some(state) = queue.pop_front() {
+            if state.0 == goal {
+                let mut path = vec![state.0];
+                let mut state = state;
+                while state != first {
+                    state = previous[&state];
+                    path.push(state.0);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for direction in [
+                Direction::West,
+                Direction::North,
+                Direction::East,
+                Direction::South,
+            ] {
+                let (position, crossing) = state;
+                let mut player = Player {
+                    x: position.x,
+                    y: position.y,
+                    floor: position.floor,
+                    direction,
+                    crossing,
+                };
+                if !player.move_forward(self) {
+                    continue;
+                }
+                let next: State = (player.position(), player.crossing);
+                if next != first && !previous.contains_key(&next) {
+                    previous.insert(next, state);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// The number of steps from `from` to every tile reachable from it,
+    /// moving like [`Map::shortest_path`].
+    pub fn distances(&self, from: Position) -> HashMap<Position, u32> {
+        let mut distances = HashMap::from([(from, 0)]);
+        let mut seen = HashSet::from([(from, None)]);
+        let mut queue = VecDeque::from([((from, None), 0)]);
+        while let Some((state, distance)) = queue.pop_front() {
+            for direction in [
+                Direction::West,
+                Direction::North,
+                Direction::East,
+                Direction::South,
+            ] {
+                let (position, crossing): State = state;
+                let mut player = Player {
+                    x: position.x,
+                    y: position.y,
+                    floor: position.floor,
+                    direction,
+                    crossing,
+                };
+                if !player.move_forward(self) {
+                    continue;
+                }
+                let next: State = (player.position(), player.crossing);
+                if seen.insert(next) {
+                    distances.entry(next.0).or_insert(distance + 1);
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+        distances
+    }
+
+    /// The plain floor tile of a cell farthest from `from`, on `floor` if
+    /// given, or `None` if there is none.
+    ///
+    /// Ties go to the first tile on the lowest floor, row by row.
+    fn farthest_from(&self, from: Position, floor: Option<u32>) -> Option<Position> {
+        self.distances(from)
+            .into_iter()
+            .filter(|(p, _)| floor.is_none_or(|f| p.floor == f))
+            .filter(|(p, _)| p.x % 2 == 1 && p.y % 2 == 1)
+            .filter(|(p, _)| self.get_on(p.floor, p.x as i32, p.y as i32) == Some(Tile::Floor))
+            .max_by_key(|&(p, distance)| (distance, Reverse((p.floor, p.y, p.x))))
+            .map(|(p, _)| p)
+    }
+
+    /// Moves the goal to the cell farthest from the start, on any floor.
+    pub fn place_goal_farthest(&mut self) {
+        if let Some(goal) = self.farthest_from(self.start(), None) {
+            (self.goal_x, self.goal_y, self.goal_floor) = (goal.x, goal.y, goal.floor);
+        }
+    }
+
+    /// Moves the start and the goal to the ends of the longest of the
+    /// shortest ways through the maze, keeping the start on the first floor.
+    ///
+    /// This takes the cell farthest from the start, then the cell of the
+    /// first floor farthest from that one as the new start, then the cell
+    /// farthest from the new start as the goal. On perfect mazes of a single
+    /// floor, no two cells are farther apart.
+    pub fn place_endpoints_farthest(&mut self) {
+        let Some(end) = self.farthest_from(self.start(), None) else {
+            return;
+        };
+        if let Some(start) = self.farthest_from(end, Some(0)) {
+            (self.start_x, self.start_y) = (start.x, start.y);
+        }
+        self.place_goal_farthest();
+    }
+
+    /// The fewest key presses taking `player` to the goal, or `None` if the
+    /// goal cannot be reached.
+    ///
+    /// Every turn and every step counts as one key press, like in
+    /// [`crate::game::GameState`], so the best way may be longer than
+    /// [`Map::shortest_path`] to spare turns.
+    pub fn fewest_key_presses(&self, player: &Player) -> Option<u32> {
+        let goal = self.goal();
+        let mut presses = HashMap::from([(*player, 0)]);
+        let mut queue = VecDeque::from([*player]);
+        while let Some(state) = queue.pop_front() {
+            let count = presses[&state];
+            if state.position() == goal {
+                return Some(count);
+            }
+            let mut next_states = [state; 4];
+            next_states[0].turn_left();
+            next_states[1].turn_right();
+            next_states[2].turn_back();
+            let moved = next_states[3].move_forward(self);
+            for next in &next_states[..if moved { 4 } else { 3 }] {
+                if let Entry::Vacant(entry) = presses.entry(*next) {
+                    entry.insert(count + 1);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{Backtracker, Kruskal, MazeGenerator, Weave};
+    use crate::map::{Size, Tile};
+    use crate::player::Direction;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// Asserts that each position of `path` is a step away from the one
+    /// before, maybe across an edge or up or down stairs.
+    fn assert_steps(map: &Map, path: &[Position]) {
+        for step in path.windows(2) {
+            let distance = |a: u32, b: u32, length: u32| {
+                let d = a.abs_diff(b);
+                d.min(length - d)
+            };
+            let moved = distance(step[0].x, step[1].x, map.width)
+                + distance(step[0].y, step[1].y, map.height);
+            assert_eq!(moved, 1, "{:?} to {:?}", step[0], step[1]);
+            assert!(step[0].floor.abs_diff(step[1].floor) <= 1);
+        }
+    }
+
+    #[test]
+    fn finds_the_shortest_way() {
+        // A ring of corridors, shorter on the left
+        let mut map = Map::filled(Size::new(7, 5).unwrap(), Tile::Wall);
+        for x in 1..6 {
+            map.set(x, 1, Tile::Floor);
+            map.set(x, 3, Tile::Floor);
+        }
+        map.set(1, 2, Tile::Floor);
+        map.set(5, 2, Tile::Floor);
+        (map.start_x, map.start_y) = (2, 1);
+        (map.goal_x, map.goal_y) = (2, 3);
+
+        let path = map.shortest_path(map.start()).unwrap();
+        assert_eq!(path.len(), 5);
+        assert_eq!((path[0], path[4]), (map.start(), map.goal()));
+        assert_steps(&map, &path);
+        assert_eq!(map.shortest_path(map.goal()), Some(vec![map.goal()]));
+
+        map.set(1, 2, Tile::Wall);
+        let path = map.shortest_path(map.start()).unwrap();
+        assert_eq!(path.len(), 9);
+        assert_steps(&map, &path);
+
+        map.set(5, 2, Tile::Wall);
+        assert_eq!(map.shortest_path(map.start()), None);
+    }
+
+    #[test]
+    fn follows_the_movement_rules() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let size = Size::new(21, 21).unwrap();
+        let floors = (0..3)
+            .map(|_| {
+                let mut map = Weave.generate(&mut rng, size);
+                map.wrap(&mut rng);
+                map
+            })
+            .collect();
+        let map = Map::stacked(&mut rng, floors);
+
+        let path = map.shortest_path(map.start()).unwrap();
+        assert_eq!(path.last(), Some(&map.goal()));
+        assert!(path.iter().any(|p| p.floor == 1));
+        assert_steps(&map, &path);
+    }
+
+    #[test]
+    fn perfect_mazes_have_one_way() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut map = Backtracker.generate(&mut rng, Size::new(31, 21).unwrap());
+        let path = map.shortest_path(map.start()).unwrap();
+        assert_steps(&map, &path);
+
+        // Without loops, the way back retraces the same tiles
+        let goal = map.goal();
+        (map.goal_x, map.goal_y) = (map.start_x, map.start_y);
+        let mut back = map.shortest_path(goal).unwrap();
+        back.reverse();
+        assert_eq!(back, path);
+    }
+
+    #[test]
+    fn counts_turns_as_key_presses() {
+        // An L-shaped corridor from (1, 1) down to (1, 3), then right to (5, 3)
+        let mut map = Map::filled(Size::new(7, 5).unwrap(), Tile::Wall);
+        map.set(1, 1, Tile::Floor);
+        map.set(1, 2, Tile::Floor);
+        for x in 1..6 {
+            map.set(x, 3, Tile::Floor);
+        }
+        (map.goal_x, map.goal_y) = (5, 3);
+        let player = |direction| Player {
+            x: 1,
+            y: 1,
+            floor: 0,
+            direction,
+            crossing: None,
+        };
+        assert_eq!(map.fewest_key_presses(&player(Direction::South)), Some(7));
+        assert_eq!(map.fewest_key_presses(&player(Direction::North)), Some(8));
+        assert_eq!(map.fewest_key_presses(&player(Direction::East)), Some(8));
+
+        map.set(1, 2, Tile::Wall);
+        assert_eq!(map.fewest_key_presses(&player(Direction::South)), None);
+    }
+
+    #[test]
+    fn prefers_fewer_turns() {
+        // An open room, where a shortest path could zigzag at every step
+        let mut map = Map::filled(Size::new(9, 5).unwrap(), Tile::Floor);
+        (map.goal_x, map.goal_y) = (7, 3);
+        let player = |direction| Player {
+            x: 1,
+            y: 1,
+            floor: 0,
+            direction,
+            crossing: None,
+        };
+        assert_eq!(map.shortest_path(map.start()).unwrap().len(), 9);
+        assert_eq!(map.fewest_key_presses(&player(Direction::East)), Some(9));
+        assert_eq!(map.fewest_key_presses(&player(Direction::West)), Some(10));
+    }
+
+    /// The plain floor tiles of cells on `floor`.
+    fn cells(map: &Map, floor: u32) -> Vec<Position> {
+        (0..map.height)
+            .flat_map(|y| (0..map.width).map(move |x| Position { x, y, floor }))
+            .filter(|p| p.x % 2 == 1 && p.y % 2 == 1)
+            .filter(|p| map.get_on(floor, p.x as i32, p.y as i32) == Some(Tile::Floor))
+            .collect()
+    }
+
+    #[test]
+    fn places_the_goal_farthest() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut map = Backtracker.generate(&mut rng, Size::new(31, 21).unwrap());
+        let distances = map.distances(map.start());
+        let farthest = cells(&map, 0).iter().map(|p| distances[p]).max().unwrap();
+        let before = map.shortest_path(map.start()).unwrap().len() as u32 - 1;
+
+        map.place_goal_farthest();
+        assert_eq!(distances[&map.goal()], farthest);
+        assert!(farthest >= before);
+        assert_eq!(
+            map.shortest_path(map.start()).unwrap().len() as u32 - 1,
+            farthest
+        );
+    }
+
+    #[test]
+    fn places_endpoints_at_the_ends_of_the_longest_way() {
+        for seed in 0..5 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut map = Kruskal.generate(&mut rng, Size::new(15, 11).unwrap());
+            let longest = cells(&map, 0)
+                .iter()
+                .flat_map(|&from| {
+                    let distances = map.distances(from);
+                    cells(&map, 0).into_iter().map(move |to| distances[&to])
+                })
+                .max()
+                .unwrap();
+
+            map.place_endpoints_farthest();
+            let path = map.shortest_path(map.start()).unwrap();
+            assert_eq!(path.len() as u32 - 1, longest, "seed {seed}");
+        }
+
+        // The start stays on the first floor
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let size = Size::new(21, 21).unwrap();
+        let floors = (0..3).map(|_| Kruskal.generate(&mut rng, size)).collect();
+        let mut map = Map::stacked(&mut rng, floors);
+        map.place_endpoints_farthest();
+        assert_eq!(
+            map.get(map.start_x as i32, map.start_y as i32),
+            Some(Tile::Floor)
+        );
+        assert_eq!(
+            map.get_on(map.goal_floor, map.goal_x as i32, map.goal_y as i32),
+            Some(Tile::Floor)
+        );
+        assert!(map.shortest_path(map.start()).is_some());
+    }
+}
@@ -0,0 +1,189 @@
+use super::{Map, Position, Tile};
+use std::collections::HashSet;
+
+/// Figures telling how hard a maze is, to show once it is solved and to
+/// compare generators with.
+///
+/// The frontends word them for players, see [`crate::view::stats_text`].
+///
+/// Tiles count, not cells, so that passages opened by braiding or across
+/// seams weigh like any other. Stairs link the tiles above and below them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MazeStats {
+    /// The steps on a shortest way from the start to the goal, or `None` if
+    /// the goal cannot be reached.
+    pub solution_length: Option<u32>,
+    /// The tiles with a single way out.
+    pub dead_ends: u32,
+    /// The tiles with three ways out or more, crossings aside.
+    pub junctions: u32,
+    /// The average steps between dead ends and junctions, also known as the
+    /// river factor: long winding corridors make it high, short branches
+    /// everywhere make it low.
+    pub average_corridor_length: f64,
+    /// The junctions met on a shortest way to the goal, where players must
+    /// choose.
+    pub decision_points: u32,
+    /// The share of cells reachable from the start, from 0 to 1.
+    pub reachable_ratio: f64,
+}
+
+impl Map {
+    /// Measures the maze, see [`MazeStats`].
+    pub fn stats(&self) -> MazeStats {
+        let mut dead_ends = 0;
+        let mut junctions = HashSet::new();
+        let mut all_ways = 0;
+        let mut corridor_ends = 0;
+        let mut cells = Vec::new();
+        for floor in 0..self.floors {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let position = Position { x, y, floor };
+                    let tile = self.get_on(floor, x as i32, y as i32);
+                    let Some(tile) = tile.filter(|tile| *tile != Tile::Wall) else {
+                        continue;
+                    };
+                    let ways = self.ways_out(position, &tile);
+                    all_ways += ways;
+                    match tile {
+                        Tile::Crossing { .. } => {}
+                        _ if ways == 1 => {
+                            dead_ends += 1;
+                            corridor_ends += 1;
+                        }
+                        _ if ways >= 3 => {
+                            junctions.insert(position);
+                            corridor_ends += ways;
+                        }
+                        _ => {}
+                    }
+                    if x % 2 == 1
+                        && y % 2 == 1
+                        && matches!(tile, Tile::Floor | Tile::Crossing { .. })
+                    {
+                        cells.push(position);
+                    }
+                }
+            }
+        }
+
+        // Each step is counted from both of its tiles, and each corridor from both of its ends
+        let steps = all_ways / 2;
+        let corridors = (corridor_ends / 2).max(1);
+        let solution = self.shortest_path(self.start());
+        let decision_points = solution.as_ref().map_or(0, |path| {
+            path[..path.len() - 1]
+                .iter()
+                .filter(|p| junctions.contains(p))
+                .count() as u32
+        });
+        let distances = self.distances(self.start());
+        let reachable = cells.iter().filter(|p| distances.contains_key(p)).count();
+
+        MazeStats {
+            solution_length: solution.map(|path| path.len() as u32 - 1),
+            dead_ends,
+            junctions: junctions.len() as u32,
+            average_corridor_length: steps as f64 / corridors as f64,
+            decision_points,
+            reachable_ratio: reachable as f64 / cells.len().max(1) as f64,
+        }
+    }
+
+    /// The open tiles next to `position` on its floor, plus the one across
+    /// the stairs if `tile` is stairs.
+    fn ways_out(&self, position: Position, tile: &Tile) -> u32 {
+        let (x, y) = (position.x as i32, position.y as i32);
+        let neighbors = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+            .into_iter()
+            .filter(|&(x, y)| {
+                self.get_on(position.floor, x, y)
+                    .is_some_and(|tile| tile != Tile::Wall)
+            })
+            .count() as u32;
+        match tile {
+            Tile::StairsUp | Tile::StairsDown => neighbors + 1,
+            _ => neighbors,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{Backtracker, MazeGenerator, Prim, Weave};
+    use crate::map::Size;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn generate(generator: &dyn MazeGenerator, seed: u64) -> Map {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        generator.generate(&mut rng, Size::new(41, 41).unwrap())
+    }
+
+    #[test]
+    fn measures_a_small_maze() {
+        // A T with its foot bent to the left, and a cell walled off
+        let mut map = Map::filled(Size::new(7, 5).unwrap(), Tile::Wall);
+        for (x, y) in [(1, 1), (2, 1), (3, 1), (4, 1), (5, 1)] {
+            map.set(x, y, Tile::Floor);
+        }
+        for (x, y) in [(3, 2), (3, 3), (2, 3), (1, 3), (5, 3)] {
+            map.set(x, y, Tile::Floor);
+        }
+        (map.goal_x, map.goal_y) = (5, 1);
+
+        let stats = map.stats();
+        assert_eq!(
+            stats,
+            MazeStats {
+                solution_length: Some(4),
+                dead_ends: 3,
+                junctions: 1,
+                average_corridor_length: 8.0 / 3.0,
+                decision_points: 1,
+                reachable_ratio: 5.0 / 6.0,
+            }
+        );
+
+        map.set(4, 1, Tile::Wall);
+        let stats = map.stats();
+        assert_eq!((stats.solution_length, stats.decision_points), (None, 0));
+        assert_eq!((stats.dead_ends, stats.junctions), (2, 0));
+    }
+
+    #[test]
+    fn tells_generators_apart() {
+        let backtracker = generate(&Backtracker, 0).stats();
+        let prim = generate(&Prim, 0).stats();
+        for stats in [&backtracker, &prim] {
+            assert_eq!(stats.reachable_ratio, 1.0);
+            assert!(stats.decision_points <= stats.junctions);
+        }
+        // The backtracker winds long corridors where Prim's algorithm branches out
+        assert!(backtracker.average_corridor_length > prim.average_corridor_length * 2.0);
+        assert!(backtracker.dead_ends < prim.dead_ends);
+    }
+
+    #[test]
+    fn crossings_are_no_junctions() {
+        let map = generate(&Weave, 0);
+        let stats = map.stats();
+        assert_eq!(stats.reachable_ratio, 1.0);
+        assert!(stats.solution_length.is_some());
+        let junctions = (0..map.height as i32)
+            .flat_map(|y| (0..map.width as i32).map(move |x| (x, y)))
+            .filter(|&(x, y)| map.get(x, y) == Some(Tile::Floor))
+            .filter(|&(x, y)| {
+                let open = |x, y| map.get(x, y).is_some_and(|tile| tile != Tile::Wall);
+                [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                    .into_iter()
+                    .filter(|&(x, y)| open(x, y))
+                    .count()
+                    >= 3
+            })
+            .count();
+        assert_eq!(stats.junctions as usize, junctions);
+    }
+}
@@ -0,0 +1,201 @@
+use super::{Map, Position, Tile};
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error("The border is open at ({}, {}) on floor {}", .0.x, .0.y, .0.floor)]
+    OpenBorder(Position),
+    #[error("The start ({}, {}) is not a floor", .0.x, .0.y)]
+    StartNotFloor(Position),
+    #[error("The goal ({}, {}) on floor {} is not a floor", .0.x, .0.y, .0.floor)]
+    GoalNotFloor(Position),
+    #[error("({}, {}) on floor {} cannot be reached from the start", .0.x, .0.y, .0.floor)]
+    Unreachable(Position),
+    #[error("The maze has {0} loops")]
+    Loops(u32),
+}
+
+impl Map {
+    /// Checks that the map is a playable maze: closed all around but for the
+    /// passages across the seams of wrapping maps, starting and ending on
+    /// plain floor, and with every open tile reachable from the start.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_border()?;
+        if self.tile_at(self.start()) != Some(Tile::Floor) {
+            return Err(ValidationError::StartNotFloor(self.start()));
+        }
+        if self.tile_at(self.goal()) != Some(Tile::Floor) {
+            return Err(ValidationError::GoalNotFloor(self.goal()));
+        }
+
+        let distances = self.distances(self.start());
+        for position in self.open_positions() {
+            // Players never stand on stairs down, they land at the foot of the stairs up below
+            let landing = match self.tile_at(position) {
+                Some(Tile::StairsDown) => Position {
+                    floor: position.floor + 1,
+                    ..position
+                },
+                _ => position,
+            };
+            if !distances.contains_key(&landing) {
+                return Err(ValidationError::Unreachable(position));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the map is a playable maze, see [`Map::validate`], with a
+    /// single way between any two tiles.
+    ///
+    /// The corridors through a crossing count as two tiles, and each pair of
+    /// stairs as a link between the floors.
+    pub fn validate_perfect(&self) -> Result<(), ValidationError> {
+        self.validate()?;
+        let (mut tiles, mut links) = (0, 0);
+        for position in self.open_positions() {
+            let Position { x, y, floor } = position;
+            let (x, y) = (x as i32, y as i32);
+            let tile = self.tile_at(position);
+            tiles += match tile {
+                Some(Tile::Crossing { .. }) => 2,
+                _ => 1,
+            };
+            if tile == Some(Tile::StairsDown) {
+                links += 1;
+            }
+            // Looking right and down only counts each link once, even across the seams
+            links += [(x + 1, y), (x, y + 1)]
+                .into_iter()
+                .filter(|&(x, y)| {
+                    self.get_on(floor, x, y)
+                        .is_some_and(|tile| tile != Tile::Wall)
+                })
+                .count() as u32;
+        }
+        // A tree of connected tiles has one link fewer than tiles
+        match links + 1 - tiles {
+            0 => Ok(()),
+            loops => Err(ValidationError::Loops(loops)),
+        }
+    }
+
+    fn tile_at(&self, position: Position) -> Option<Tile> {
+        self.get_on(position.floor, position.x as i32, position.y as i32)
+    }
+
+    fn open_positions(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..self.floors)
+            .flat_map(move |floor| {
+                (0..self.height)
+                    .flat_map(move |y| (0..self.width).map(move |x| Position { x, y, floor }))
+            })
+            .filter(|&position| {
+                self.tile_at(position)
+                    .is_some_and(|tile| tile != Tile::Wall)
+            })
+    }
+
+    /// Border tiles may only open onto the tile facing them across the seam.
+    fn validate_border(&self) -> Result<(), ValidationError> {
+        let (last_x, last_y) = (self.width - 1, self.height - 1);
+        for position in self.open_positions() {
+            let Position { x, y, .. } = position;
+            let on_side = x == 0 || x == last_x;
+            let on_top_or_bottom = y == 0 || y == last_y;
+            let facing = match (on_side, on_top_or_bottom) {
+                (false, false) => continue,
+                (true, true) => None,
+                (true, false) => Some(Position {
+                    x: last_x - x,
+                    ..position
+                }),
+                (false, true) => Some(Position {
+                    y: last_y - y,
+                    ..position
+                }),
+            };
+            let is_seam = self.wraps
+                && facing.is_some_and(|p| self.tile_at(p).is_some_and(|tile| tile != Tile::Wall));
+            if !is_seam {
+                return Err(ValidationError::OpenBorder(position));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{Kruskal, MazeGenerator, Weave};
+    use crate::map::Size;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn accepts_perfect_mazes() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let size = Size::new(21, 21).unwrap();
+        assert_eq!(Kruskal.generate(&mut rng, size).validate_perfect(), Ok(()));
+        assert_eq!(Weave.generate(&mut rng, size).validate_perfect(), Ok(()));
+
+        let floors = (0..3).map(|_| Weave.generate(&mut rng, size)).collect();
+        let map = Map::stacked(&mut rng, floors);
+        assert_eq!(map.validate_perfect(), Ok(()));
+
+        // Seams join corridors already joined
+        let mut map = Kruskal.generate(&mut rng, size);
+        map.wrap(&mut rng);
+        assert_eq!(map.validate(), Ok(()));
+        assert!(matches!(
+            map.validate_perfect(),
+            Err(ValidationError::Loops(_))
+        ));
+    }
+
+    #[test]
+    fn finds_what_is_wrong() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let maze = Kruskal.generate(&mut rng, Size::new(11, 11).unwrap());
+        let at = |x, y| Position { x, y, floor: 0 };
+
+        let mut map = maze.clone();
+        map.set(0, 5, Tile::Floor);
+        assert_eq!(map.validate(), Err(ValidationError::OpenBorder(at(0, 5))));
+        map.wraps = true;
+        assert_eq!(map.validate(), Err(ValidationError::OpenBorder(at(0, 5))));
+        map.set(10, 5, Tile::Floor);
+        assert_eq!(map.validate(), Ok(()));
+
+        let mut map = maze.clone();
+        map.set(1, 1, Tile::Wall);
+        assert_eq!(
+            map.validate(),
+            Err(ValidationError::StartNotFloor(at(1, 1)))
+        );
+
+        let mut map = maze.clone();
+        map.set(9, 9, Tile::StairsUp);
+        assert_eq!(map.validate(), Err(ValidationError::GoalNotFloor(at(9, 9))));
+
+        let mut map = maze.clone();
+        map.set(9, 8, Tile::Wall);
+        map.set(8, 9, Tile::Wall);
+        assert!(matches!(
+            map.validate(),
+            Err(ValidationError::Unreachable(_))
+        ));
+
+        let mut map = maze.clone();
+        map.braid(&mut rng, 100);
+        assert_eq!(map.validate(), Ok(()));
+        assert!(matches!(
+            map.validate_perfect(),
+            Err(ValidationError::Loops(_))
+        ));
+        assert_eq!(
+            ValidationError::Loops(3).to_string(),
+            "The maze has 3 loops"
+        );
+    }
+}
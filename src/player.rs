@@ -1,42 +1,419 @@
-#[derive(Clone, Copy)]
-pub enum Direction {
-    West,
-    North,
-    East,
-    South,
-}
-
-pub struct Player {
-    pub x: u32,
-    pub y: u32,
-    pub direction: Direction,
-}
-
-pub struct Sight<'a, 'b> {
-    map: &'a crate::map::Map,
-    player: &'b Player,
-}
-
-impl Player {
-    pub fn sight<'a, 'b>(&'b self, map: &'a crate::map::Map) -> Sight<'a, 'b> {
-        Sight { map, player: self }
-    }
-}
-
-impl<'a, 'b> Sight<'a, 'b> {
-    pub fn get(&self, forward: u32, horizontal: i32) -> crate::map::Tile {
-        let (x_diff, y_diff) = match self.player.direction {
-            Direction::West => (-(forward as i32), -horizontal),
-            Direction::North => (horizontal, -(forward as i32)),
-            Direction::East => (forward as i32, horizontal),
-            Direction::South => (-horizontal, forward as i32),
-        };
-
-        self.map
-            .get(
-                self.player.x.saturating_add_signed(x_diff) as i32,
-                self.player.y.saturating_add_signed(y_diff) as i32,
-            )
-            .unwrap_or(crate::map::Tile::Wall)
-    }
-}
+use crate::map::{Axis, Map, Position, Tile};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    West,
+    North,
+    East,
+    South,
+}
+
+impl Direction {
+    pub fn turned_left(self) -> Self {
+        match self {
+            Direction::West => Direction::South,
+            Direction::North => Direction::West,
+            Direction::East => Direction::North,
+            Direction::South => Direction::East,
+        }
+    }
+
+    pub fn turned_right(self) -> Self {
+        match self {
+            Direction::West => Direction::North,
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+        }
+    }
+
+    pub fn turned_back(self) -> Self {
+        match self {
+            Direction::West => Direction::East,
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+        }
+    }
+
+    pub fn axis(self) -> Axis {
+        match self {
+            Direction::West | Direction::East => Axis::Horizontal,
+            Direction::North | Direction::South => Axis::Vertical,
+        }
+    }
+
+    /// The (x, y) step of moving one tile forward.
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::West => (-1, 0),
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Player {
+    pub x: u32,
+    pub y: u32,
+    /// The floor of the map the player is on.
+    pub floor: u32,
+    pub direction: Direction,
+    /// The corridor followed through the crossing the player stands on,
+    /// which it can only be left along.
+    pub crossing: Option<Axis>,
+}
+
+/// What stands on a tile in sight, as far as drawing it goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Face {
+    Open,
+    Wall,
+    /// A low wall, along the edges of a bridge.
+    Railing,
+}
+
+pub struct Sight<'a, 'b> {
+    map: &'a Map,
+    player: &'b Player,
+}
+
+impl Player {
+    pub fn sight<'a, 'b>(&'b self, map: &'a Map) -> Sight<'a, 'b> {
+        Sight { map, player: self }
+    }
+
+    pub fn position(&self) -> Position {
+        Position {
+            x: self.x,
+            y: self.y,
+            floor: self.floor,
+        }
+    }
+
+    pub fn turn_left(&mut self) {
+        self.direction = self.direction.turned_left();
+    }
+
+    pub fn turn_right(&mut self) {
+        self.direction = self.direction.turned_right();
+    }
+
+    pub fn turn_back(&mut self) {
+        self.direction = self.direction.turned_back();
+    }
+
+    /// Steps one tile forward unless a wall or the map edge is in the way,
+    /// going across the edge of wrapping maps, taking the stairs stepped on
+    /// and keeping to the corridor followed through crossings. Returns
+    /// whether the player moved.
+    pub fn move_forward(&mut self, map: &Map) -> bool {
+        if self
+            .crossing
+            .is_some_and(|axis| axis != self.direction.axis())
+        {
+            return false;
+        }
+        let point_diff = self.direction.offset();
+        let Some((new_x, new_y)) =
+            map.position(self.x as i32 + point_diff.0, self.y as i32 + point_diff.1)
+        else {
+            return false;
+        };
+        let tile = map
+            .get_on(self.floor, new_x as i32, new_y as i32)
+            .unwrap_or(Tile::Wall);
+        if tile == Tile::Wall {
+            return false;
+        }
+
+        self.x = new_x;
+        self.y = new_y;
+        self.crossing = None;
+        match tile {
+            Tile::StairsUp => self.floor -= 1,
+            Tile::StairsDown => self.floor += 1,
+            Tile::Crossing { .. } => self.crossing = Some(self.direction.axis()),
+            _ => {}
+        }
+        true
+    }
+}
+
+impl<'a, 'b> Sight<'a, 'b> {
+    pub fn direction(&self) -> Direction {
+        self.player.direction
+    }
+
+    /// Whether the cell `forward` tiles ahead is a crossing whose bridge
+    /// passes over the corridor looked along, or over the one followed
+    /// through the crossing the player stands on.
+    pub fn is_under_bridge(&self, forward: u32) -> bool {
+        match self.get(forward, 0) {
+            Tile::Crossing { over } => over != self.walked_axis(forward),
+            _ => false,
+        }
+    }
+
+    /// How the tile `forward` tiles ahead and `horizontal` to the right
+    /// looks. Besides walls, the passages of a crossing that the corridor
+    /// followed through it cannot turn into are closed: by railings along
+    /// the bridge, by walls along the passage under it.
+    pub fn face(&self, forward: u32, horizontal: i32) -> Face {
+        if self.get(forward, horizontal) == Tile::Wall {
+            return Face::Wall;
+        }
+        // The crossing the tile leads out of
+        let crossing = match (forward, horizontal) {
+            (1, 0) => 0,
+            (_, -1 | 1) => forward,
+            _ => return Face::Open,
+        };
+        let Tile::Crossing { over } = self.get(crossing, 0) else {
+            return Face::Open;
+        };
+        let walked = self.walked_axis(crossing);
+        let leads_along = (horizontal == 0) == (self.player.direction.axis() == walked);
+        match (leads_along, walked == over) {
+            (true, _) => Face::Open,
+            (false, true) => Face::Railing,
+            (false, false) => Face::Wall,
+        }
+    }
+
+    /// The corridor followed through the cell `forward` tiles ahead.
+    fn walked_axis(&self, forward: u32) -> Axis {
+        match self.player.crossing {
+            Some(axis) if forward == 0 => axis,
+            _ => self.player.direction.axis(),
+        }
+    }
+
+    pub fn get(&self, forward: u32, horizontal: i32) -> Tile {
+        let (x_diff, y_diff) = match self.player.direction {
+            Direction::West => (-(forward as i32), -horizontal),
+            Direction::North => (horizontal, -(forward as i32)),
+            Direction::East => (forward as i32, horizontal),
+            Direction::South => (-horizontal, forward as i32),
+        };
+
+        self.map
+            .get_on(
+                self.player.floor,
+                self.player.x as i32 + x_diff,
+                self.player.y as i32 + y_diff,
+            )
+            .unwrap_or(Tile::Wall)
+    }
+}
+
+/// Two corridors of 7x7 tiles crossing at (3, 3), the horizontal one on
+/// the bridge.
+#[cfg(test)]
+pub(crate) fn crossroads() -> Map {
+    let mut map = Map::filled(crate::map::Size::new(7, 7).unwrap(), Tile::Wall);
+    for i in 1..6 {
+        map.set(i, 3, Tile::Floor);
+        map.set(3, i, Tile::Floor);
+    }
+    map.set(
+        3,
+        3,
+        Tile::Crossing {
+            over: Axis::Horizontal,
+        },
+    );
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{BarKnocking, MazeGenerator};
+    use crate::map::Size;
+    use rand::SeedableRng;
+
+    const ALL_DIRECTIONS: [Direction; 4] = [
+        Direction::West,
+        Direction::North,
+        Direction::East,
+        Direction::South,
+    ];
+
+    fn map() -> Map {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+        BarKnocking.generate(&mut rng, Size::default())
+    }
+
+    #[test]
+    fn turns_are_consistent() {
+        for d in ALL_DIRECTIONS {
+            assert_eq!(d.turned_left().turned_right(), d);
+            assert_eq!(d.turned_right().turned_right(), d.turned_back());
+            assert_eq!(d.turned_left().turned_left(), d.turned_back());
+            assert_eq!(d.turned_back().turned_back(), d);
+        }
+    }
+
+    #[test]
+    fn cannot_walk_through_walls() {
+        let map = map();
+        let mut player = Player {
+            x: map.start_x,
+            y: map.start_y,
+            floor: 0,
+            direction: Direction::North,
+            crossing: None,
+        };
+        assert!(!player.move_forward(&map));
+        player.turn_left();
+        assert!(!player.move_forward(&map));
+        assert_eq!((player.x, player.y), (1, 1));
+    }
+
+    #[test]
+    fn sight_looks_forward() {
+        let map = map();
+        for direction in ALL_DIRECTIONS {
+            let player = Player {
+                x: 1,
+                y: 1,
+                floor: 0,
+                direction,
+                crossing: None,
+            };
+            let (dx, dy) = direction.offset();
+            let expected = map.get(1 + dx, 1 + dy).unwrap();
+            assert_eq!(player.sight(&map).get(1, 0), expected);
+        }
+    }
+
+    #[test]
+    fn walks_across_wrapping_edges() {
+        let mut map = Map::filled(Size::new(5, 5).unwrap(), Tile::Wall);
+        for x in 0..5 {
+            map.set(x, 1, Tile::Floor);
+        }
+        let mut player = Player {
+            x: 1,
+            y: 1,
+            floor: 0,
+            direction: Direction::West,
+            crossing: None,
+        };
+        assert!(player.move_forward(&map));
+        assert!(!player.move_forward(&map));
+
+        map.wraps = true;
+        assert_eq!(player.sight(&map).get(2, 0), Tile::Floor);
+        assert_eq!(player.sight(&map).get(1, 1), Tile::Wall);
+        assert!(player.move_forward(&map));
+        assert_eq!((player.x, player.y), (4, 1));
+        assert!(player.move_forward(&map));
+        assert_eq!((player.x, player.y), (3, 1));
+    }
+
+    #[test]
+    fn takes_the_stairs() {
+        let size = Size::new(5, 5).unwrap();
+        let mut map = Map::stacked(
+            &mut rand_chacha::ChaCha8Rng::seed_from_u64(0),
+            vec![
+                Map::filled(size, Tile::Floor),
+                Map::filled(size, Tile::Floor),
+            ],
+        );
+        map.set_on(0, 2, 1, Tile::StairsDown);
+        map.set_on(1, 2, 1, Tile::StairsUp);
+        map.set_on(1, 3, 1, Tile::Wall);
+        let mut player = Player {
+            x: 1,
+            y: 1,
+            floor: 0,
+            direction: Direction::East,
+            crossing: None,
+        };
+        assert_eq!(player.sight(&map).get(2, 0), Tile::Floor);
+        assert!(player.move_forward(&map));
+        assert_eq!((player.x, player.y, player.floor), (2, 1, 1));
+        assert_eq!(player.sight(&map).get(1, 0), Tile::Wall);
+        assert!(!player.move_forward(&map));
+
+        player.turn_back();
+        assert!(player.move_forward(&map));
+        player.turn_back();
+        assert!(player.move_forward(&map));
+        assert_eq!((player.x, player.y, player.floor), (2, 1, 0));
+    }
+
+    #[test]
+    fn keeps_to_the_corridor_through_crossings() {
+        let map = crossroads();
+        let mut player = Player {
+            x: 3,
+            y: 1,
+            floor: 0,
+            direction: Direction::South,
+            crossing: None,
+        };
+        assert!(player.move_forward(&map));
+        assert!(player.move_forward(&map));
+        assert_eq!(player.crossing, Some(Axis::Vertical));
+        player.turn_left();
+        assert!(!player.move_forward(&map));
+        player.turn_left();
+        assert!(player.move_forward(&map));
+        assert_eq!((player.x, player.y, player.crossing), (3, 2, None));
+
+        player.turn_back();
+        assert!(player.move_forward(&map));
+        assert!(player.move_forward(&map));
+        assert_eq!((player.x, player.y), (3, 4));
+    }
+
+    #[test]
+    fn closes_the_passages_crossings_cannot_turn_into() {
+        let map = crossroads();
+        let sight = |x, y, direction, crossing| {
+            let player = Player {
+                x,
+                y,
+                floor: 0,
+                direction,
+                crossing,
+            };
+            let faces = [(0, -1), (0, 1), (1, 0), (2, -1), (2, 0), (2, 1)]
+                .map(|(forward, horizontal)| player.sight(&map).face(forward, horizontal));
+            (faces, player.sight(&map).is_under_bridge(0))
+        };
+        use Face::{Open, Railing, Wall};
+
+        // Walking up to the crossing, on the bridge and under it
+        let (faces, _) = sight(1, 3, Direction::East, None);
+        assert_eq!(faces, [Wall, Wall, Open, Railing, Open, Railing]);
+        let (faces, _) = sight(3, 1, Direction::South, None);
+        assert_eq!(faces, [Wall, Wall, Open, Wall, Open, Wall]);
+
+        // Standing on the crossing, along the corridor followed and across it
+        let on_bridge = Some(Axis::Horizontal);
+        let under_bridge = Some(Axis::Vertical);
+        assert_eq!(
+            sight(3, 3, Direction::East, on_bridge),
+            ([Railing, Railing, Open, Wall, Open, Wall], false)
+        );
+        assert_eq!(
+            sight(3, 3, Direction::North, on_bridge),
+            ([Open, Open, Railing, Wall, Open, Wall], false)
+        );
+        assert_eq!(
+            sight(3, 3, Direction::South, under_bridge),
+            ([Wall, Wall, Open, Wall, Open, Wall], true)
+        );
+        assert_eq!(
+            sight(3, 3, Direction::West, under_bridge),
+            ([Open, Open, Wall, Wall, Open, Wall], true)
+        );
+    }
+}
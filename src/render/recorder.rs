@@ -0,0 +1,159 @@
+use super::{Canvas, Color, Point, Rect, Renderer};
+use std::cell::{Cell, RefCell};
+use std::convert::Infallible;
+use std::rc::Rc;
+
+/// A drawing call captured by a [`Recorder`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Clear(Color),
+    CopyFrom {
+        dest_rect: Rect,
+        src: String,
+        src_x: i32,
+        src_y: i32,
+    },
+    FillRect(Rect, Color),
+    DrawRect(Rect, Color),
+    DrawPolygon {
+        points: Vec<Point>,
+        x: i32,
+        y: i32,
+        pen: Color,
+        fill: Color,
+        angle: f32,
+    },
+    DrawText {
+        text: String,
+        x: i32,
+        y: i32,
+        font: String,
+        color: Color,
+    },
+}
+
+type Log = Rc<RefCell<Vec<(String, Command)>>>;
+
+/// A renderer that draws nothing and logs every call instead.
+///
+/// Bitmaps and fonts are represented by their names: a loaded bitmap by its
+/// file path, an offscreen surface by `offscreen<N>`, a font by `face:size`.
+/// Calls on offscreen surfaces go to the same log, tagged with their name.
+pub struct Recorder {
+    name: String,
+    size: (u32, u32),
+    log: Log,
+    offscreen_count: Rc<Cell<u32>>,
+}
+
+impl Recorder {
+    pub fn new(width: u32, height: u32) -> Self {
+        Recorder {
+            name: String::from("screen"),
+            size: (width, height),
+            log: Log::default(),
+            offscreen_count: Rc::default(),
+        }
+    }
+
+    /// Every call made so far, with the name of the canvas it was made on.
+    pub fn commands(&self) -> Vec<(String, Command)> {
+        self.log.borrow().clone()
+    }
+
+    pub fn clear_commands(&self) {
+        self.log.borrow_mut().clear();
+    }
+
+    fn record(&self, command: Command) {
+        self.log.borrow_mut().push((self.name.clone(), command));
+    }
+}
+
+impl Canvas for Recorder {
+    type Bitmap = String;
+    type Font = String;
+
+    fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn clear(&mut self, color: Color) {
+        self.record(Command::Clear(color));
+    }
+
+    fn copy_from(&mut self, dest_rect: &Rect, src: &String, src_x: i32, src_y: i32) {
+        self.record(Command::CopyFrom {
+            dest_rect: *dest_rect,
+            src: src.clone(),
+            src_x,
+            src_y,
+        });
+    }
+
+    fn fill_rect(&mut self, rect: &Rect, color: Color) {
+        self.record(Command::FillRect(*rect, color));
+    }
+
+    fn draw_rect(&mut self, rect: &Rect, color: Color) {
+        self.record(Command::DrawRect(*rect, color));
+    }
+
+    fn draw_polygon(
+        &mut self,
+        points: &[Point],
+        x: i32,
+        y: i32,
+        pen: Color,
+        fill: Color,
+        angle: f32,
+    ) {
+        self.record(Command::DrawPolygon {
+            points: points.to_vec(),
+            x,
+            y,
+            pen,
+            fill,
+            angle,
+        });
+    }
+
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, font: &String, color: Color) {
+        self.record(Command::DrawText {
+            text: text.to_owned(),
+            x,
+            y,
+            font: font.clone(),
+            color,
+        });
+    }
+}
+
+impl Renderer for Recorder {
+    type Error = Infallible;
+    type Offscreen = Recorder;
+
+    fn create_offscreen(&mut self, width: u32, height: u32) -> Result<Recorder, Infallible> {
+        let count = self.offscreen_count.get();
+        self.offscreen_count.set(count + 1);
+        let name = format!("offscreen{count}");
+        Ok(Recorder {
+            name,
+            size: (width, height),
+            log: self.log.clone(),
+            offscreen_count: self.offscreen_count.clone(),
+        })
+    }
+
+    fn get_bitmap(&mut self, offscreen: &Recorder) -> Result<String, Infallible> {
+        Ok(offscreen.name.clone())
+    }
+
+    fn load_bitmap(&mut self, file_path: &str) -> Result<String, Infallible> {
+        Ok(file_path.to_owned())
+    }
+
+    fn create_font(&mut self, face_name: &str, size: i32) -> Result<String, Infallible> {
+        Ok(format!("{face_name}:{size}"))
+    }
+}
@@ -0,0 +1,338 @@
+//! A pure-Rust renderer drawing into an RGBA buffer, with no GPU or window.
+
+mod font;
+
+pub use crate::bmp::{BmpError, Image};
+
+use super::{color_rgb, Canvas, Color, Point, Rect, Renderer};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Cannot load a bitmap from file ({file_path}): {source}")]
+    BitmapLoad {
+        file_path: String,
+        #[source]
+        source: BmpError,
+    },
+    #[error("Cannot read a bitmap from file ({file_path}): {source}")]
+    BitmapRead {
+        file_path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+pub struct Font {
+    size: i32,
+}
+
+/// A CPU framebuffer; both the primary and the offscreen canvases of the
+/// software backend.
+///
+/// Bitmaps are loaded from BMP files. Text uses a built-in ASCII font
+/// regardless of the requested face, completed by a Japanese font of the
+/// system, and characters that neither font has are drawn as boxes.
+pub struct Framebuffer {
+    image: Image,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Framebuffer {
+            image: Image::new(width, height, color_rgb(0, 0, 0)),
+        }
+    }
+
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    pub fn into_image(self) -> Image {
+        self.image
+    }
+
+    /// How far `text` reaches to the right when drawn with `font`.
+    pub fn text_width(&self, text: &str, font: &Font) -> i32 {
+        font::text_width(text, font.size)
+    }
+}
+
+impl Canvas for Framebuffer {
+    type Bitmap = Image;
+    type Font = Font;
+
+    fn get_size(&self) -> (u32, u32) {
+        (self.image.width(), self.image.height())
+    }
+
+    fn clear(&mut self, color: Color) {
+        self.image = Image::new(self.image.width(), self.image.height(), color);
+    }
+
+    fn copy_from(&mut self, dest_rect: &Rect, src: &Image, src_x: i32, src_y: i32) {
+        for dy in 0..dest_rect.height() {
+            let sy = src_y + dy;
+            if sy < 0 || sy as u32 >= src.height() {
+                continue;
+            }
+            for dx in 0..dest_rect.width() {
+                let sx = src_x + dx;
+                if sx < 0 || sx as u32 >= src.width() {
+                    continue;
+                }
+                let color = src.get_pixel(sx as u32, sy as u32);
+                self.image
+                    .set_pixel(dest_rect.left + dx, dest_rect.top + dy, color);
+            }
+        }
+    }
+
+    fn fill_rect(&mut self, rect: &Rect, color: Color) {
+        for y in rect.top..rect.bottom {
+            for x in rect.left..rect.right {
+                self.image.set_pixel(x, y, color);
+            }
+        }
+    }
+
+    fn draw_rect(&mut self, rect: &Rect, color: Color) {
+        for x in rect.left..rect.right {
+            self.image.set_pixel(x, rect.top, color);
+            self.image.set_pixel(x, rect.bottom - 1, color);
+        }
+        for y in rect.top..rect.bottom {
+            self.image.set_pixel(rect.left, y, color);
+            self.image.set_pixel(rect.right - 1, y, color);
+        }
+    }
+
+    fn draw_polygon(
+        &mut self,
+        points: &[Point],
+        x: i32,
+        y: i32,
+        pen: Color,
+        fill: Color,
+        angle: f32,
+    ) {
+        if points.is_empty() {
+            return;
+        }
+        let (min_x, max_x) = min_max(points.iter().map(|p| p.x as f32));
+        let (min_y, max_y) = min_max(points.iter().map(|p| p.y as f32));
+        let (center_x, center_y) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let vertices: Vec<(f32, f32)> = points
+            .iter()
+            .map(|p| {
+                let (dx, dy) = (p.x as f32 - center_x, p.y as f32 - center_y);
+                (
+                    center_x + dx * cos - dy * sin + x as f32,
+                    center_y + dx * sin + dy * cos + y as f32,
+                )
+            })
+            .collect();
+        let edges: Vec<Edge> = vertices
+            .iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .map(|(a, b)| (*a, *b))
+            .collect();
+
+        let (left, right) = min_max(vertices.iter().map(|v| v.0));
+        let (top, bottom) = min_max(vertices.iter().map(|v| v.1));
+        for py in (top.floor() as i32 - 1)..=(bottom.ceil() as i32) {
+            for px in (left.floor() as i32 - 1)..=(right.ceil() as i32) {
+                let center = (px as f32 + 0.5, py as f32 + 0.5);
+                // Like Direct2D, the outline is stroked first and the fill covers its inner half
+                if contains(&edges, center) {
+                    self.image.set_pixel(px, py, fill);
+                } else if edges
+                    .iter()
+                    .any(|&(a, b)| distance_to_segment(center, a, b) <= 0.5)
+                {
+                    self.image.set_pixel(px, py, pen);
+                }
+            }
+        }
+    }
+
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, font: &Font, color: Color) {
+        font::draw_text(&mut self.image, text, x, y, font.size, color);
+    }
+}
+
+impl Renderer for Framebuffer {
+    type Error = Error;
+    type Offscreen = Framebuffer;
+
+    fn create_offscreen(&mut self, width: u32, height: u32) -> Result<Framebuffer, Error> {
+        Ok(Framebuffer::new(width, height))
+    }
+
+    fn get_bitmap(&mut self, offscreen: &Framebuffer) -> Result<Image, Error> {
+        Ok(offscreen.image.clone())
+    }
+
+    fn load_bitmap(&mut self, file_path: &str) -> Result<Image, Error> {
+        let data = std::fs::read(file_path).map_err(|e| Error::BitmapRead {
+            file_path: file_path.to_owned(),
+            source: e,
+        })?;
+        Image::from_bmp(&data).map_err(|e| Error::BitmapLoad {
+            file_path: file_path.to_owned(),
+            source: e,
+        })
+    }
+
+    fn create_font(&mut self, _face_name: &str, size: i32) -> Result<Font, Error> {
+        Ok(Font { size })
+    }
+}
+
+type Edge = ((f32, f32), (f32, f32));
+
+fn min_max(values: impl Iterator<Item = f32>) -> (f32, f32) {
+    values.fold((f32::MAX, f32::MIN), |(min, max), v| {
+        (min.min(v), max.max(v))
+    })
+}
+
+/// Even-odd test of whether `point` is inside the polygon made of `edges`.
+fn contains(edges: &[Edge], point: (f32, f32)) -> bool {
+    let mut inside = false;
+    for &((ax, ay), (bx, by)) in edges {
+        if (ay > point.1) != (by > point.1) && point.0 < ax + (point.1 - ay) / (by - ay) * (bx - ax)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn distance_to_segment(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_squared).clamp(0.0, 1.0)
+    };
+    let (nx, ny) = (a.0 + t * dx, a.1 + t * dy);
+    ((p.0 - nx).powi(2) + (p.1 - ny).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameState, Settings};
+    use crate::map::Size;
+    use crate::render::{point, rect_wh, Command, Recorder};
+    use crate::view::{self, GameView};
+
+    const WHITE: Color = color_rgb(255, 255, 255);
+    const BLACK: Color = color_rgb(0, 0, 0);
+
+    #[test]
+    fn fills_and_outlines_rects() {
+        let mut fb = Framebuffer::new(8, 8);
+        fb.clear(WHITE);
+        fb.fill_rect(&rect_wh(1, 1, 2, 2), BLACK);
+        fb.draw_rect(&rect_wh(4, 4, 4, 4), BLACK);
+        let image = fb.image();
+        assert_eq!(image.get_pixel(1, 1), BLACK);
+        assert_eq!(image.get_pixel(2, 2), BLACK);
+        assert_eq!(image.get_pixel(3, 3), WHITE);
+        assert_eq!(image.get_pixel(4, 4), BLACK);
+        assert_eq!(image.get_pixel(7, 7), BLACK);
+        assert_eq!(image.get_pixel(5, 5), WHITE);
+    }
+
+    #[test]
+    fn copies_and_clips_bitmaps() {
+        let mut src = Image::new(4, 4, WHITE);
+        src.set_pixel(3, 3, BLACK);
+        let mut fb = Framebuffer::new(4, 4);
+        fb.copy_from(&rect_wh(-1, -1, 4, 4), &src, 1, 1);
+        assert_eq!(fb.image().get_pixel(1, 1), BLACK);
+        assert_eq!(fb.image().get_pixel(0, 0), WHITE);
+        assert_eq!(fb.image().get_pixel(2, 2), BLACK);
+    }
+
+    #[test]
+    fn rotates_polygons_clockwise() {
+        // A triangle pointing left
+        let triangle = [point(0, 5), point(10, 0), point(10, 10)];
+        let tip_row = |fb: &Framebuffer| {
+            (0..10)
+                .find(|&y| (0..10).any(|x| fb.image().get_pixel(x, y) != WHITE))
+                .unwrap()
+        };
+
+        let mut fb = Framebuffer::new(10, 10);
+        fb.clear(WHITE);
+        fb.draw_polygon(&triangle, 0, 0, BLACK, BLACK, 90.0);
+        // Now pointing up: the first painted row is narrow
+        let top = tip_row(&fb);
+        let width = (0..10)
+            .filter(|&x| fb.image().get_pixel(x, top + 1) != WHITE)
+            .count();
+        assert!(width <= 3);
+        assert_ne!(fb.image().get_pixel(5, 9), WHITE);
+    }
+
+    #[test]
+    fn draws_a_frame() {
+        let mut state = GameState::new([5; 32]);
+        state.toggle_mini_map();
+        let mut view = GameView::new();
+        let mut fb = Framebuffer::new(view::WIDTH, view::HEIGHT);
+        view.draw(&state, &mut fb).unwrap();
+        let image = fb.image();
+
+        // Frames around the 3D view and the mini map
+        assert_eq!(image.get_pixel(47, 47), BLACK);
+        assert_eq!(image.get_pixel(48 * 2 + 256 - 1, 48 - 1), BLACK);
+        // The goal tile on the mini map
+        assert_eq!(image.get_pixel(352 + 237, 48 + 237), color_rgb(255, 0, 0));
+        // The wall texture reaches the 3D view
+        assert_ne!(image.get_pixel(48 + 128, 48 + 128), WHITE);
+    }
+
+    #[test]
+    fn fits_the_goal_messages_in_the_frame() {
+        let settings = Settings {
+            size: Size::new(5, 5).unwrap(),
+            ..Default::default()
+        };
+        let mut state = GameState::with_settings([5; 32], settings);
+        // Keeps the left hand on the wall until the goal is reached
+        for _ in 0..100 {
+            state.turn_left();
+            for _ in 0..4 {
+                let before = (state.player().x, state.player().y);
+                state.move_forward();
+                if before != (state.player().x, state.player().y) {
+                    break;
+                }
+                state.turn_right();
+            }
+        }
+        assert!(state.is_goal());
+
+        let mut rt = Recorder::new(view::WIDTH, view::HEIGHT);
+        GameView::new().draw(&state, &mut rt).unwrap();
+        let mut fb = Framebuffer::new(view::WIDTH, view::HEIGHT);
+        let mut texts = 0;
+        for command in rt.commands() {
+            let (_, Command::DrawText { text, x, font, .. }) = command else {
+                continue;
+            };
+            let size = font.rsplit_once(':').unwrap().1.parse().unwrap();
+            let font = fb.create_font("", size).unwrap();
+            let right = x + fb.text_width(&text, &font);
+            assert!(right <= view::WIDTH as i32, "{text} reaches {right}");
+            texts += 1;
+        }
+        assert!(texts >= 3);
+    }
+}
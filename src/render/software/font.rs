@@ -0,0 +1,256 @@
+//! A built-in 8x16 bitmap font covering printable ASCII, completed by a
+//! Japanese font of the system for the messages of the game.
+//!
+//! The glyphs were rasterized from DejaVu Sans Mono at 14px. Characters
+//! that neither font has are drawn as boxes so that text layout is still
+//! visible.
+
+use super::Image;
+use crate::render::Color;
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use std::sync::OnceLock;
+
+const GLYPH_WIDTH: i32 = 8;
+const GLYPH_HEIGHT: i32 = 16;
+/// The pixel size the glyphs were rasterized at.
+const BASE_SIZE: i32 = 14;
+
+/// Where Linux distributions, macOS and Windows keep a font with Japanese
+/// glyphs, the first one found being used.
+const SYSTEM_FONTS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/opentype/ipafont-gothic/ipag.ttf",
+    "/usr/share/fonts/truetype/fonts-japanese-gothic.ttf",
+    "/System/Library/Fonts/ヒラギノ角ゴシック W3.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "C:\\Windows\\Fonts\\msgothic.ttc",
+    "C:\\Windows\\Fonts\\meiryo.ttc",
+];
+
+/// The system font for the glyphs missing from the built-in ones, read once.
+fn system_font() -> Option<&'static FontVec> {
+    static FONT: OnceLock<Option<FontVec>> = OnceLock::new();
+    FONT.get_or_init(|| {
+        SYSTEM_FONTS.iter().find_map(|path| {
+            let data = std::fs::read(path).ok()?;
+            FontVec::try_from_vec(data).ok()
+        })
+    })
+    .as_ref()
+}
+
+/// Bit 7 of each row is the leftmost pixel.
+#[rustfmt::skip]
+const GLYPHS: [[u8; 16]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x00, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // '!'
+    [0x00, 0x00, 0x00, 0x28, 0x28, 0x28, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x00, 0x00, 0x00, 0x10, 0x14, 0x34, 0x7E, 0x28, 0x28, 0xFC, 0x48, 0x50, 0x00, 0x00, 0x00, 0x00], // '#'
+    [0x00, 0x00, 0x00, 0x10, 0x3C, 0x70, 0x50, 0x30, 0x1C, 0x16, 0x14, 0x7C, 0x10, 0x00, 0x00, 0x00], // '$'
+    [0x00, 0x00, 0x00, 0x00, 0xF0, 0x90, 0x72, 0x18, 0x6C, 0x1A, 0x12, 0x0C, 0x00, 0x00, 0x00, 0x00], // '%'
+    [0x00, 0x00, 0x00, 0x38, 0x60, 0x60, 0x20, 0x70, 0x5A, 0x8E, 0x44, 0x7E, 0x00, 0x00, 0x00, 0x00], // '&'
+    [0x00, 0x00, 0x00, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '\''
+    [0x00, 0x00, 0x00, 0x08, 0x18, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x08, 0x08, 0x00, 0x00], // '('
+    [0x00, 0x00, 0x00, 0x20, 0x10, 0x10, 0x18, 0x18, 0x18, 0x18, 0x10, 0x10, 0x30, 0x20, 0x00, 0x00], // ')'
+    [0x00, 0x00, 0x00, 0x10, 0x54, 0x38, 0x3C, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '*'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x10, 0x10, 0x7E, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00], // ','
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // '.'
+    [0x00, 0x00, 0x00, 0x04, 0x04, 0x08, 0x08, 0x10, 0x10, 0x20, 0x20, 0x60, 0x40, 0x00, 0x00, 0x00], // '/'
+    [0x00, 0x00, 0x00, 0x38, 0x6C, 0x44, 0x44, 0x54, 0x44, 0x44, 0x64, 0x38, 0x00, 0x00, 0x00, 0x00], // '0'
+    [0x00, 0x00, 0x00, 0x38, 0x38, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, 0x00, 0x00, 0x00, 0x00], // '1'
+    [0x00, 0x00, 0x00, 0x78, 0x4C, 0x04, 0x04, 0x08, 0x18, 0x30, 0x60, 0x7C, 0x00, 0x00, 0x00, 0x00], // '2'
+    [0x00, 0x00, 0x00, 0x78, 0x0C, 0x04, 0x0C, 0x38, 0x04, 0x04, 0x04, 0x78, 0x00, 0x00, 0x00, 0x00], // '3'
+    [0x00, 0x00, 0x00, 0x08, 0x1C, 0x1C, 0x2C, 0x4C, 0x4C, 0x7E, 0x0C, 0x0C, 0x00, 0x00, 0x00, 0x00], // '4'
+    [0x00, 0x00, 0x00, 0x7C, 0x60, 0x40, 0x78, 0x0C, 0x04, 0x04, 0x04, 0x78, 0x00, 0x00, 0x00, 0x00], // '5'
+    [0x00, 0x00, 0x00, 0x3C, 0x60, 0x40, 0x58, 0x64, 0x44, 0x44, 0x64, 0x3C, 0x00, 0x00, 0x00, 0x00], // '6'
+    [0x00, 0x00, 0x00, 0x7C, 0x04, 0x0C, 0x08, 0x08, 0x18, 0x10, 0x10, 0x20, 0x00, 0x00, 0x00, 0x00], // '7'
+    [0x00, 0x00, 0x00, 0x38, 0x64, 0x44, 0x6C, 0x38, 0x44, 0x44, 0x44, 0x3C, 0x00, 0x00, 0x00, 0x00], // '8'
+    [0x00, 0x00, 0x00, 0x38, 0x4C, 0x44, 0x44, 0x44, 0x3C, 0x04, 0x0C, 0x78, 0x00, 0x00, 0x00, 0x00], // '9'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x10, 0x00, 0x00, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // ':'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x10, 0x00, 0x00, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00], // ';'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x70, 0x60, 0x38, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00], // '<'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7C, 0x7C, 0x00, 0xFE, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '='
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x70, 0x1C, 0x06, 0x38, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00], // '>'
+    [0x00, 0x00, 0x00, 0x38, 0x0C, 0x04, 0x0C, 0x18, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // '?'
+    [0x00, 0x00, 0x00, 0x00, 0x3C, 0x42, 0x4A, 0x96, 0xA2, 0xB2, 0x9E, 0x40, 0x60, 0x1C, 0x00, 0x00], // '@'
+    [0x00, 0x00, 0x00, 0x10, 0x38, 0x28, 0x28, 0x2C, 0x64, 0x7C, 0x46, 0xC2, 0x00, 0x00, 0x00, 0x00], // 'A'
+    [0x00, 0x00, 0x00, 0x78, 0x4C, 0x44, 0x4C, 0x7C, 0x44, 0x46, 0x46, 0x7C, 0x00, 0x00, 0x00, 0x00], // 'B'
+    [0x00, 0x00, 0x00, 0x1C, 0x20, 0x40, 0x40, 0x40, 0x40, 0x40, 0x60, 0x3C, 0x00, 0x00, 0x00, 0x00], // 'C'
+    [0x00, 0x00, 0x00, 0x70, 0x4C, 0x44, 0x44, 0x46, 0x44, 0x44, 0x4C, 0x78, 0x00, 0x00, 0x00, 0x00], // 'D'
+    [0x00, 0x00, 0x00, 0x7C, 0x60, 0x40, 0x60, 0x7C, 0x40, 0x40, 0x40, 0x7E, 0x00, 0x00, 0x00, 0x00], // 'E'
+    [0x00, 0x00, 0x00, 0x7C, 0x60, 0x60, 0x60, 0x7C, 0x60, 0x60, 0x60, 0x60, 0x00, 0x00, 0x00, 0x00], // 'F'
+    [0x00, 0x00, 0x00, 0x3C, 0x64, 0x40, 0x40, 0x4C, 0x46, 0x46, 0x66, 0x3C, 0x00, 0x00, 0x00, 0x00], // 'G'
+    [0x00, 0x00, 0x00, 0x44, 0x44, 0x44, 0x44, 0x7C, 0x44, 0x44, 0x44, 0x44, 0x00, 0x00, 0x00, 0x00], // 'H'
+    [0x00, 0x00, 0x00, 0x7C, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x7C, 0x00, 0x00, 0x00, 0x00], // 'I'
+    [0x00, 0x00, 0x00, 0x3C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x08, 0x78, 0x00, 0x00, 0x00, 0x00], // 'J'
+    [0x00, 0x00, 0x00, 0x46, 0x4C, 0x58, 0x50, 0x70, 0x58, 0x4C, 0x44, 0x46, 0x00, 0x00, 0x00, 0x00], // 'K'
+    [0x00, 0x00, 0x00, 0x40, 0x60, 0x60, 0x60, 0x60, 0x60, 0x60, 0x60, 0x7E, 0x00, 0x00, 0x00, 0x00], // 'L'
+    [0x00, 0x00, 0x00, 0x46, 0xE6, 0xEE, 0xEA, 0xDA, 0xD2, 0xC2, 0xC2, 0xC2, 0x00, 0x00, 0x00, 0x00], // 'M'
+    [0x00, 0x00, 0x00, 0x44, 0x64, 0x64, 0x54, 0x54, 0x5C, 0x4C, 0x4C, 0x44, 0x00, 0x00, 0x00, 0x00], // 'N'
+    [0x00, 0x00, 0x00, 0x38, 0x64, 0x44, 0x46, 0x46, 0x46, 0x44, 0x64, 0x38, 0x00, 0x00, 0x00, 0x00], // 'O'
+    [0x00, 0x00, 0x00, 0x78, 0x6C, 0x46, 0x46, 0x7C, 0x70, 0x40, 0x40, 0x40, 0x00, 0x00, 0x00, 0x00], // 'P'
+    [0x00, 0x00, 0x00, 0x38, 0x64, 0x44, 0x46, 0x46, 0x46, 0x44, 0x64, 0x38, 0x0C, 0x00, 0x00, 0x00], // 'Q'
+    [0x00, 0x00, 0x00, 0x78, 0x4C, 0x44, 0x44, 0x78, 0x48, 0x44, 0x46, 0x42, 0x00, 0x00, 0x00, 0x00], // 'R'
+    [0x00, 0x00, 0x00, 0x3C, 0x64, 0x40, 0x60, 0x38, 0x0C, 0x04, 0x04, 0x7C, 0x00, 0x00, 0x00, 0x00], // 'S'
+    [0x00, 0x00, 0x00, 0xFE, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // 'T'
+    [0x00, 0x00, 0x00, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x3C, 0x00, 0x00, 0x00, 0x00], // 'U'
+    [0x00, 0x00, 0x00, 0x42, 0x46, 0x44, 0x44, 0x2C, 0x28, 0x28, 0x38, 0x18, 0x00, 0x00, 0x00, 0x00], // 'V'
+    [0x00, 0x00, 0x00, 0x82, 0x82, 0x82, 0xD2, 0x5A, 0x6E, 0x6C, 0x6C, 0x64, 0x00, 0x00, 0x00, 0x00], // 'W'
+    [0x00, 0x00, 0x00, 0x42, 0x64, 0x2C, 0x38, 0x18, 0x38, 0x2C, 0x44, 0xC2, 0x00, 0x00, 0x00, 0x00], // 'X'
+    [0x00, 0x00, 0x00, 0x42, 0x44, 0x6C, 0x28, 0x18, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // 'Y'
+    [0x00, 0x00, 0x00, 0x7E, 0x06, 0x0C, 0x08, 0x18, 0x10, 0x20, 0x60, 0x7E, 0x00, 0x00, 0x00, 0x00], // 'Z'
+    [0x00, 0x00, 0x00, 0x18, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x18, 0x00, 0x00], // '['
+    [0x00, 0x00, 0x00, 0x40, 0x40, 0x20, 0x20, 0x10, 0x10, 0x18, 0x08, 0x0C, 0x04, 0x00, 0x00, 0x00], // '\\'
+    [0x00, 0x00, 0x00, 0x38, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x30, 0x00, 0x00], // ']'
+    [0x00, 0x00, 0x00, 0x10, 0x38, 0x44, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFE, 0x00], // '_'
+    [0x00, 0x00, 0x20, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0x4C, 0x04, 0x7C, 0x44, 0x44, 0x7C, 0x00, 0x00, 0x00, 0x00], // 'a'
+    [0x00, 0x00, 0x00, 0x40, 0x40, 0x58, 0x64, 0x44, 0x46, 0x44, 0x64, 0x7C, 0x00, 0x00, 0x00, 0x00], // 'b'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x1C, 0x24, 0x60, 0x40, 0x40, 0x20, 0x3C, 0x00, 0x00, 0x00, 0x00], // 'c'
+    [0x00, 0x00, 0x00, 0x04, 0x04, 0x34, 0x6C, 0x44, 0x44, 0x44, 0x44, 0x3C, 0x00, 0x00, 0x00, 0x00], // 'd'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x64, 0x44, 0x7E, 0x40, 0x40, 0x3C, 0x00, 0x00, 0x00, 0x00], // 'e'
+    [0x00, 0x00, 0x00, 0x1C, 0x10, 0x7C, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // 'f'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x34, 0x6C, 0x44, 0x44, 0x44, 0x44, 0x3C, 0x04, 0x0C, 0x30, 0x00], // 'g'
+    [0x00, 0x00, 0x00, 0x40, 0x40, 0x58, 0x6C, 0x44, 0x44, 0x44, 0x44, 0x44, 0x00, 0x00, 0x00, 0x00], // 'h'
+    [0x00, 0x00, 0x00, 0x10, 0x00, 0x30, 0x10, 0x10, 0x10, 0x10, 0x10, 0x7C, 0x00, 0x00, 0x00, 0x00], // 'i'
+    [0x00, 0x00, 0x00, 0x18, 0x00, 0x30, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x30, 0x60, 0x00], // 'j'
+    [0x00, 0x00, 0x00, 0x60, 0x60, 0x64, 0x6C, 0x78, 0x78, 0x68, 0x64, 0x66, 0x00, 0x00, 0x00, 0x00], // 'k'
+    [0x00, 0x00, 0x00, 0x70, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1C, 0x00, 0x00, 0x00, 0x00], // 'l'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x24, 0x5E, 0x52, 0x52, 0x52, 0x52, 0x52, 0x00, 0x00, 0x00, 0x00], // 'm'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x58, 0x6C, 0x44, 0x44, 0x44, 0x44, 0x44, 0x00, 0x00, 0x00, 0x00], // 'n'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0x6C, 0x44, 0x44, 0x44, 0x44, 0x38, 0x00, 0x00, 0x00, 0x00], // 'o'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x58, 0x64, 0x44, 0x46, 0x44, 0x64, 0x7C, 0x40, 0x40, 0x00, 0x00], // 'p'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x34, 0x6C, 0x44, 0x44, 0x44, 0x44, 0x3C, 0x04, 0x04, 0x04, 0x00], // 'q'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x2C, 0x30, 0x30, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0x00], // 'r'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0x60, 0x60, 0x38, 0x0C, 0x04, 0x78, 0x00, 0x00, 0x00, 0x00], // 's'
+    [0x00, 0x00, 0x00, 0x00, 0x30, 0x7C, 0x30, 0x30, 0x30, 0x30, 0x10, 0x1C, 0x00, 0x00, 0x00, 0x00], // 't'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x44, 0x44, 0x44, 0x44, 0x44, 0x64, 0x3C, 0x00, 0x00, 0x00, 0x00], // 'u'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x44, 0x44, 0x2C, 0x28, 0x38, 0x18, 0x00, 0x00, 0x00, 0x00], // 'v'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x82, 0x82, 0xD2, 0x56, 0x6C, 0x6C, 0x6C, 0x00, 0x00, 0x00, 0x00], // 'w'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x44, 0x64, 0x38, 0x10, 0x38, 0x2C, 0x44, 0x00, 0x00, 0x00, 0x00], // 'x'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x44, 0x64, 0x2C, 0x28, 0x18, 0x10, 0x10, 0x30, 0x40, 0x00], // 'y'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3C, 0x0C, 0x08, 0x10, 0x30, 0x20, 0x7C, 0x00, 0x00, 0x00, 0x00], // 'z'
+    [0x00, 0x00, 0x00, 0x1C, 0x10, 0x10, 0x10, 0x10, 0x60, 0x10, 0x10, 0x10, 0x10, 0x0C, 0x00, 0x00], // '{'
+    [0x00, 0x00, 0x00, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00], // '|'
+    [0x00, 0x00, 0x00, 0x70, 0x10, 0x10, 0x10, 0x10, 0x0C, 0x10, 0x10, 0x10, 0x10, 0x70, 0x00, 0x00], // '}'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x5E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '~'
+];
+
+/// Draws `text` with its top-left corner at (`x`, `y`), `size` pixels per em.
+pub fn draw_text(image: &mut Image, text: &str, x: i32, y: i32, size: i32, color: Color) {
+    let half_width = (GLYPH_WIDTH * size + BASE_SIZE / 2) / BASE_SIZE;
+    let cell_height = (GLYPH_HEIGHT * size + BASE_SIZE / 2) / BASE_SIZE;
+    let mut cursor = x;
+    for c in text.chars() {
+        let code = c as u32;
+        match code {
+            0x20..=0x7E => draw_glyph(image, code, cursor, y, half_width, cell_height, color),
+            // Full-width forms of ASCII, e.g. '！' and '：'
+            0xFF01..=0xFF5E => {
+                let offset = (size - half_width) / 2;
+                let ascii = code - 0xFEE0;
+                draw_glyph(
+                    image,
+                    ascii,
+                    cursor + offset,
+                    y,
+                    half_width,
+                    cell_height,
+                    color,
+                );
+            }
+            // Ideographic space
+            0x3000 => {}
+            _ => {
+                let width = char_width(code, size);
+                let baseline = y + 12 * cell_height / GLYPH_HEIGHT;
+                let drawn = system_font().is_some_and(|font| {
+                    draw_font_glyph(image, font, c, cursor, baseline, size, color)
+                });
+                if !drawn {
+                    draw_missing_glyph(image, cursor, y, width, cell_height, color);
+                }
+            }
+        }
+        cursor += char_width(code, size);
+    }
+}
+
+/// How far `text` drawn with `size` pixels per em reaches to the right.
+pub fn text_width(text: &str, size: i32) -> i32 {
+    text.chars().map(|c| char_width(c as u32, size)).sum()
+}
+
+/// Full-width characters take a whole em, the others half of one.
+fn char_width(code: u32, size: i32) -> i32 {
+    if code >= 0x1100 {
+        size
+    } else {
+        (GLYPH_WIDTH * size + BASE_SIZE / 2) / BASE_SIZE
+    }
+}
+
+fn draw_glyph(image: &mut Image, code: u32, x: i32, y: i32, width: i32, height: i32, color: Color) {
+    let glyph = &GLYPHS[(code - 0x20) as usize];
+    for dy in 0..height {
+        let row = glyph[(dy * GLYPH_HEIGHT / height) as usize];
+        for dx in 0..width {
+            if row & (0x80 >> (dx * GLYPH_WIDTH / width)) != 0 {
+                image.set_pixel(x + dx, y + dy, color);
+            }
+        }
+    }
+}
+
+/// Draws `c` from `font` in one color, so that it looks like the built-in
+/// glyphs, or returns false if the font has no glyph for it.
+fn draw_font_glyph(
+    image: &mut Image,
+    font: &FontVec,
+    c: char,
+    x: i32,
+    baseline: i32,
+    size: i32,
+    color: Color,
+) -> bool {
+    let id = font.glyph_id(c);
+    if id.0 == 0 {
+        return false;
+    }
+    // A pixel scale spans the ascent and the descent, not the em
+    let units_per_em = font.units_per_em().unwrap_or(font.height_unscaled());
+    let scale = PxScale::from(size as f32 * font.height_unscaled() / units_per_em);
+    let glyph = id.with_scale_and_position(scale, point(x as f32, baseline as f32));
+    if let Some(outline) = font.as_scaled(scale).outline_glyph(glyph) {
+        let bounds = outline.px_bounds();
+        outline.draw(|dx, dy, coverage| {
+            if coverage >= 0.5 {
+                let px = bounds.min.x as i32 + dx as i32;
+                let py = bounds.min.y as i32 + dy as i32;
+                image.set_pixel(px, py, color);
+            }
+        });
+    }
+    true
+}
+
+fn draw_missing_glyph(image: &mut Image, x: i32, y: i32, width: i32, height: i32, color: Color) {
+    // Spans the rows between the cap height and the baseline of the ASCII glyphs
+    let top = y + 3 * height / GLYPH_HEIGHT;
+    let bottom = y + 12 * height / GLYPH_HEIGHT;
+    let left = x + 1;
+    let right = x + width - 2;
+    for px in left..=right {
+        image.set_pixel(px, top, color);
+        image.set_pixel(px, bottom, color);
+    }
+    for py in top..=bottom {
+        image.set_pixel(left, py, color);
+        image.set_pixel(right, py, color);
+    }
+}
@@ -0,0 +1,431 @@
+//! Draws a [`GameState`] as a grid of characters, for terminal frontends.
+//!
+//! The corridor view follows the same perspective as the wall bitmap used
+//! by [`crate::view`], with each character standing for 8x16 of its pixels.
+
+use crate::game::GameState;
+use crate::map::{Axis, Tile};
+use crate::player::{Direction, Face, Sight};
+use crate::view;
+
+/// The size of the corridor view in characters.
+pub const CORRIDOR_WIDTH: usize = 32;
+pub const CORRIDOR_HEIGHT: usize = 16;
+
+const PIXEL_SIZE: f32 = 256.0;
+const CHAR_WIDTH: f32 = PIXEL_SIZE / CORRIDOR_WIDTH as f32;
+const CHAR_HEIGHT: f32 = PIXEL_SIZE / CORRIDOR_HEIGHT as f32;
+
+/// The most tiles shown on each axis of the mini map.
+pub const MINI_MAP_TILES: u32 = 21;
+
+/// Half the size in pixels of the square where each distance plane crosses
+/// the view: plane `d` is the face of the cells `d` steps ahead.
+const PLANES: [f32; 4] = [128.0, 108.0, 56.0, 20.0];
+
+/// How far to the sides cells are checked, per distance.
+const HORIZONTAL_RANGE: [i32; 4] = [1, 1, 2, 3];
+
+/// Fill characters for wall faces, per distance of the plane they start at.
+const SHADES: [char; 4] = ['█', '▓', '▒', '░'];
+
+/// The fill character for bridges, seen from below or from the side.
+const BRIDGE: char = '=';
+/// The fill character for the railings along bridges.
+const RAILING: char = '#';
+
+/// Draws the whole screen: seed, corridor, mini map when shown, and message.
+pub fn draw(state: &GameState) -> Vec<String> {
+    let corridor = draw_corridor(&state.player().sight(state.map()));
+    let mini_map = if state.shows_mini_map() {
+        draw_mini_map(state)
+    } else {
+        Vec::new()
+    };
+
+    let border = format!("+{}+", "-".repeat(CORRIDOR_WIDTH));
+    let mut left = vec![border.clone()];
+    left.extend(corridor.iter().map(|line| format!("|{line}|")));
+    left.push(border);
+
+    let mut lines = vec![
+        format!(
+            "seed: {} ({})",
+            state.seed_text(),
+            state.settings().description()
+        ),
+        String::new(),
+    ];
+    for i in 0..left.len().max(mini_map.len()) {
+        let l = left
+            .get(i)
+            .cloned()
+            .unwrap_or_else(|| " ".repeat(CORRIDOR_WIDTH + 2));
+        match mini_map.get(i) {
+            Some(r) => lines.push(format!("{l}  {r}")),
+            None => lines.push(l),
+        }
+    }
+    lines.push(String::new());
+    if state.is_goal() {
+        lines.push(format!(
+            "ゴール！　スコア：{}点　{}手（最短{}手）",
+            state.score(),
+            state.key_press_count(),
+            state.par()
+        ));
+        lines.push(String::from("リスタート：Enterキー　終了：ESCキー"));
+        lines.push(view::stats_text(state.stats()));
+    } else {
+        lines.push(String::from("移動：矢印キー マップ：Mキー 終了：ESCキー"));
+    }
+    lines
+}
+
+/// Draws the first-person view, `CORRIDOR_HEIGHT` lines of `CORRIDOR_WIDTH` characters.
+pub fn draw_corridor(sight: &Sight) -> Vec<String> {
+    let mut grid = vec![vec![' '; CORRIDOR_WIDTH]; CORRIDOR_HEIGHT];
+
+    // Paint from far to near, and from the sides to the center, so nearer faces cover farther ones
+    for forward in (0..PLANES.len()).rev() {
+        let range = HORIZONTAL_RANGE[forward];
+        let mut horizontals: Vec<i32> = (-range..=range).collect();
+        horizontals.sort_by_key(|h| -h.abs());
+        for horizontal in horizontals {
+            let face = sight.face(forward as u32, horizontal);
+            if face == Face::Open {
+                continue;
+            }
+            if forward > 0 {
+                fill_front_face(&mut grid, forward, horizontal, face);
+            }
+            if horizontal != 0 && forward + 1 < PLANES.len() {
+                fill_side_face(&mut grid, forward, horizontal, face);
+            }
+        }
+        if sight.is_under_bridge(forward as u32) {
+            if forward + 1 < PLANES.len() {
+                fill_bridge(&mut grid, forward);
+            }
+            if forward > 0 {
+                fill_overpass(&mut grid, forward);
+            }
+        }
+    }
+
+    grid.into_iter()
+        .map(|row| row.into_iter().collect())
+        .collect()
+}
+
+fn fill_front_face(grid: &mut [Vec<char>], forward: usize, horizontal: i32, face: Face) {
+    let half = PLANES[forward];
+    let left = PIXEL_SIZE / 2.0 + (horizontal as f32 * 2.0 - 1.0) * half;
+    let right = left + half * 2.0;
+    fill_face(grid, forward, face, |x, y| {
+        left < x && x < right && (PIXEL_SIZE / 2.0 - y).abs() < half
+    });
+}
+
+/// Fills the face of a cell beside the corridor that looks toward its center.
+fn fill_side_face(grid: &mut [Vec<char>], forward: usize, horizontal: i32, face: Face) {
+    let (near, far) = (PLANES[forward], PLANES[forward + 1]);
+    // The edge of the face toward the center, as a multiple of the half size of a plane
+    let edge = horizontal as f32 * 2.0 - horizontal.signum() as f32;
+    let near_x = PIXEL_SIZE / 2.0 + edge * near;
+    let far_x = PIXEL_SIZE / 2.0 + edge * far;
+    let (left, right) = (near_x.min(far_x), near_x.max(far_x));
+    fill_face(grid, forward, face, |x, y| {
+        if x <= left || right <= x {
+            return false;
+        }
+        let t = (x - near_x) / (far_x - near_x);
+        let half = near + (far - near) * t;
+        (PIXEL_SIZE / 2.0 - y).abs() < half
+    });
+}
+
+/// Fills the ceiling of the cell `forward` tiles ahead, where a bridge
+/// crosses over the corridor.
+fn fill_bridge(grid: &mut [Vec<char>], forward: usize) {
+    let (near, far) = (PLANES[forward], PLANES[forward + 1]);
+    fill(grid, BRIDGE, |x, y| {
+        let above = PIXEL_SIZE / 2.0 - y;
+        far < above && above < near && (PIXEL_SIZE / 2.0 - x).abs() < above
+    });
+}
+
+/// Fills the front of the bridge crossing over the cell `forward` tiles
+/// ahead, along the top of its near face.
+fn fill_overpass(grid: &mut [Vec<char>], forward: usize) {
+    let half = PLANES[forward];
+    fill(grid, BRIDGE, |x, y| {
+        let above = PIXEL_SIZE / 2.0 - y;
+        half / 2.0 < above && above < half && (PIXEL_SIZE / 2.0 - x).abs() < half
+    });
+}
+
+/// Fills a whole wall face in the shade of its distance, or the part of
+/// a railing face below eye level.
+fn fill_face(grid: &mut [Vec<char>], forward: usize, face: Face, shape: impl Fn(f32, f32) -> bool) {
+    match face {
+        Face::Open => {}
+        Face::Wall => fill(grid, SHADES[forward], shape),
+        Face::Railing => fill(grid, RAILING, |x, y| y > PIXEL_SIZE / 2.0 && shape(x, y)),
+    }
+}
+
+/// Sets every character whose center pixel is inside `shape` to `c`.
+fn fill(grid: &mut [Vec<char>], c: char, shape: impl Fn(f32, f32) -> bool) {
+    for (row, line) in grid.iter_mut().enumerate() {
+        for (column, cell) in line.iter_mut().enumerate() {
+            let x = (column as f32 + 0.5) * CHAR_WIDTH;
+            let y = (row as f32 + 0.5) * CHAR_HEIGHT;
+            if shape(x, y) {
+                *cell = c;
+            }
+        }
+    }
+}
+
+/// Draws the map with two characters per tile, marking the start, the goal
+/// and the player facing its direction. Passages across the edges of
+/// wrapping maps are shaded, and maps of several floors show the floor of
+/// the player, with its stairs, below it.
+///
+/// Maps larger than `MINI_MAP_TILES` are cropped to the part around the player.
+pub fn draw_mini_map(state: &GameState) -> Vec<String> {
+    let map = state.map();
+    let player = state.player();
+    let xs = visible_range(player.x, map.width);
+    let ys = visible_range(player.y, map.height);
+    let mut lines: Vec<String> = ys
+        .map(|y| {
+            xs.clone()
+                .map(|x| {
+                    if (x, y) == (player.x, player.y) {
+                        match player.direction {
+                            Direction::West => "<-",
+                            Direction::North => "/\\",
+                            Direction::East => "->",
+                            Direction::South => "\\/",
+                        }
+                    } else if (x, y, player.floor) == (map.start_x, map.start_y, 0) {
+                        "S "
+                    } else if (x, y, player.floor) == (map.goal_x, map.goal_y, map.goal_floor) {
+                        "G "
+                    } else {
+                        let is_edge = x == 0 || y == 0 || x == map.width - 1 || y == map.height - 1;
+                        match map.get_on(player.floor, x as i32, y as i32) {
+                            Some(Tile::Floor) if is_edge => "░░",
+                            Some(Tile::Floor) => "  ",
+                            Some(Tile::StairsUp) => "▲ ",
+                            Some(Tile::StairsDown) => "▼ ",
+                            Some(Tile::Crossing {
+                                over: Axis::Horizontal,
+                            }) => "══",
+                            Some(Tile::Crossing {
+                                over: Axis::Vertical,
+                            }) => "║ ",
+                            _ => "██",
+                        }
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    if map.floors > 1 {
+        lines.push(format!("floor {}/{}", player.floor + 1, map.floors));
+    }
+    lines
+}
+
+/// The tiles shown on one axis of the mini map, centered on `center` when cropped.
+fn visible_range(center: u32, length: u32) -> std::ops::Range<u32> {
+    let shown = length.min(MINI_MAP_TILES);
+    let start = center.saturating_sub(shown / 2).min(length - shown);
+    start..start + shown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Settings;
+    use crate::map::Size;
+    use crate::player::{crossroads, Player};
+
+    #[test]
+    fn corridor_snapshot() {
+        let mut state = GameState::new([5; 32]);
+        state.move_forward();
+        let corridor = draw_corridor(&state.player().sight(state.map()));
+        assert_eq!(
+            corridor.join("\n"),
+            [
+                "█                              █",
+                "██                            ██",
+                "██ ▓▓                      ▓▓ ██",
+                "██ ▓▓▓▓                  ▓▓▓▓ ██",
+                "██ ▓▓▓▓▓▓              ▓▓▓▓▓▓ ██",
+                "██▒▓▓▓▓▓▓▒▒          ▒▒▓▓▓▓▓▓▒██",
+                "██▒▓▓▓▓▓▓▒▒▒▒      ▒▒▒▒▓▓▓▓▓▓▒██",
+                "██▒▓▓▓▓▓▓▒▒▒▒      ▒▒▒▒▓▓▓▓▓▓▒██",
+                "██▒▓▓▓▓▓▓▒▒▒▒      ▒▒▒▒▓▓▓▓▓▓▒██",
+                "██▒▓▓▓▓▓▓▒▒▒▒      ▒▒▒▒▓▓▓▓▓▓▒██",
+                "██▒▓▓▓▓▓▓▒▒          ▒▒▓▓▓▓▓▓▒██",
+                "██ ▓▓▓▓▓▓              ▓▓▓▓▓▓ ██",
+                "██ ▓▓▓▓                  ▓▓▓▓ ██",
+                "██ ▓▓                      ▓▓ ██",
+                "██                            ██",
+                "█                              █",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn mini_map_snapshot() {
+        let mut state = GameState::new([5; 32]);
+        state.move_forward();
+        assert_eq!(
+            draw_mini_map(&state).join("\n"),
+            [
+                "██████████████████████████████████████████",
+                "██S                             ██  ██  ██",
+                "██\\/██  ██████████████  ██████████  ██  ██",
+                "██  ██  ██          ██                  ██",
+                "██  ██  ██  ██████  ██████  ██  ██████████",
+                "██  ██          ██  ██  ██  ██  ██      ██",
+                "██████  ██  ██  ██████  ██  ██  ██  ██  ██",
+                "██      ██  ██  ██          ██      ██  ██",
+                "██████  ██████████  ██████████████  ██████",
+                "██  ██          ██              ██      ██",
+                "██  ██  ██████████  ██  ██████  ██████████",
+                "██  ██  ██  ██      ██      ██      ██  ██",
+                "██  ██  ██  ██  ██  ██  ██  ██  ██████  ██",
+                "██              ██  ██  ██  ██      ██  ██",
+                "██  ██████████████████████████  ██  ██  ██",
+                "██      ██  ██  ██          ██  ██  ██  ██",
+                "██████  ██  ██  ██  ██████████  ██  ██  ██",
+                "██                  ██                  ██",
+                "██  ██  ██████████████  ██████████████  ██",
+                "██  ██                              ██G ██",
+                "██████████████████████████████████████████",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn mini_map_is_hidden_until_toggled() {
+        let mut state = GameState::new([5; 32]);
+        let lines = draw(&state);
+        assert!(!lines.iter().any(|l| l.contains("G ")));
+        state.toggle_mini_map();
+        let lines = draw(&state);
+        assert!(lines.iter().any(|l| l.contains("\\/")));
+        assert!(lines.iter().any(|l| l.contains("G ")));
+    }
+
+    #[test]
+    fn large_mini_maps_follow_the_player() {
+        let settings = Settings {
+            size: Size::new(101, 41).unwrap(),
+            ..Default::default()
+        };
+        let state = GameState::with_settings([5; 32], settings);
+        let mini_map = draw_mini_map(&state);
+        assert_eq!(mini_map.len(), MINI_MAP_TILES as usize);
+        assert!(mini_map
+            .iter()
+            .all(|l| l.chars().count() == 2 * MINI_MAP_TILES as usize));
+        assert!(mini_map[1].starts_with("██\\/"));
+
+        // Centered on the player, except near the borders
+        assert_eq!(visible_range(50, 101), 40..61);
+        assert_eq!(visible_range(99, 101), 80..101);
+        assert_eq!(visible_range(3, 21), 0..21);
+    }
+
+    #[test]
+    fn mini_map_shades_wrapping_edges() {
+        let state = GameState::new([5; 32]);
+        assert!(!draw_mini_map(&state).iter().any(|l| l.contains('░')));
+
+        let settings = Settings {
+            wrap: true,
+            ..Default::default()
+        };
+        let state = GameState::with_settings([5; 32], settings);
+        let mini_map = draw_mini_map(&state);
+        let (first, last) = (&mini_map[0], &mini_map[mini_map.len() - 1]);
+        assert!(first.contains("░░"));
+        assert_eq!(first, last);
+    }
+
+    #[test]
+    fn mini_map_shows_the_floor() {
+        let settings = Settings {
+            floors: 2,
+            ..Default::default()
+        };
+        let state = GameState::with_settings([5; 32], settings);
+        let mini_map = draw_mini_map(&state);
+        assert_eq!(mini_map.len(), 22);
+        assert_eq!(mini_map[21], "floor 1/2");
+        assert!(mini_map.iter().any(|l| l.contains("▼ ")));
+        assert!(!mini_map
+            .iter()
+            .any(|l| l.contains("▲ ") || l.contains("G ")));
+    }
+
+    #[test]
+    fn corridor_shows_bridges_overhead() {
+        let map = crossroads();
+        let corridor = |x, y, direction| {
+            let player = Player {
+                x,
+                y,
+                floor: 0,
+                direction,
+                crossing: None,
+            };
+            draw_corridor(&player.sight(&map)).concat()
+        };
+        assert!(corridor(3, 1, Direction::South).contains(BRIDGE));
+        assert!(corridor(3, 5, Direction::North).contains(BRIDGE));
+        assert!(!corridor(1, 3, Direction::East).contains(BRIDGE));
+    }
+
+    #[test]
+    fn corridor_shows_railings_and_overpasses() {
+        let map = crossroads();
+        let corridor = |x, y, direction, crossing| {
+            let player = Player {
+                x,
+                y,
+                floor: 0,
+                direction,
+                crossing,
+            };
+            draw_corridor(&player.sight(&map))
+        };
+
+        // On the bridge, railings below eye level on both sides
+        let on_bridge = corridor(3, 3, Direction::East, Some(Axis::Horizontal));
+        assert!(on_bridge[..8].iter().all(|l| !l.contains(RAILING)));
+        for line in &on_bridge[8..] {
+            assert!(
+                line.starts_with(RAILING) && line.ends_with(RAILING),
+                "{line}"
+            );
+        }
+
+        // Across the bridge, from the corridor passing under it
+        let under = corridor(3, 1, Direction::South, None);
+        assert!(under.concat().chars().all(|c| c != RAILING));
+        assert_eq!(under[4], "██ ▓▓▓▓▓▓              ▓▓▓▓▓▓ ██");
+        assert_eq!(under[5], "██▒▓▓▓▓▓▓==============▓▓▓▓▓▓▒██");
+        assert_eq!(under[6], "██▒▓▓▓▓▓▓▒▒▒▒======▒▒▒▒▓▓▓▓▓▓▒██");
+    }
+}
@@ -0,0 +1,566 @@
+use crate::game::GameState;
+use crate::map::{MazeStats, Tile};
+use crate::player::{Direction, Face, Sight};
+use crate::render::{color_rgb, point, rect, rect_wh, Canvas, Color, Rect, Renderer};
+
+/// The size of a whole frame in pixels.
+pub const WIDTH: u32 = 48 * 3 + 256 * 2;
+pub const HEIGHT: u32 = 48 * 2 + 256;
+
+const WALL_BITMAP_PATH: &str = "assets/wall.bmp";
+
+const BLACK: Color = color_rgb(0, 0, 0);
+const WHITE: Color = color_rgb(255, 255, 255);
+const START_COLOR: Color = color_rgb(0, 255, 255);
+const GOAL_COLOR: Color = color_rgb(255, 0, 0);
+/// The color of the passages across the edges of wrapping maps.
+const SEAM_COLOR: Color = color_rgb(255, 200, 0);
+const STAIRS_UP_COLOR: Color = color_rgb(0, 160, 0);
+const STAIRS_DOWN_COLOR: Color = color_rgb(160, 0, 160);
+const BRIDGE_COLOR: Color = color_rgb(128, 96, 64);
+
+/// The faces of the wall bitmap drawn for the cells at each distance, in
+/// drawing order: how far aside the cell is, the rectangle drawn to as left,
+/// top, width and height, and where it is copied from.
+type WallFaces = &'static [(i32, (i32, i32, i32, i32), (i32, i32))];
+const WALL_FACES: [WallFaces; 4] = [
+    &[
+        (-1, (0, 0, 20, 256), (256, 0)),
+        (1, (236, 0, 20, 256), (492, 0)),
+    ],
+    &[
+        (-1, (0, 20, 73, 216), (512, 20)),
+        (1, (183, 20, 73, 216), (695, 20)),
+        (0, (20, 20, 216, 216), (276, 20)),
+    ],
+    &[
+        (-2, (0, 72, 21, 112), (768, 72)),
+        (-1, (21, 72, 89, 112), (788, 72)),
+        (1, (147, 72, 89, 112), (915, 72)),
+        (2, (235, 72, 21, 112), (1003, 72)),
+        (0, (72, 72, 112, 112), (584, 72)),
+    ],
+    &[
+        (-3, (0, 108, 40 - 12, 40), (876 + 12, 108)),
+        (-2, (28, 108, 40, 40), (876, 108)),
+        (-1, (68, 108, 40, 40), (876, 108)),
+        (1, (148, 108, 40, 40), (876, 108)),
+        (2, (188, 108, 40, 40), (876, 108)),
+        (3, (228, 108, 256 - 228, 40), (876, 108)),
+        (0, (108, 108, 40, 40), (876, 108)),
+    ],
+];
+
+/// Half the size in pixels of the faces of the cells 0 to 3 tiles ahead.
+const PLANES: [i32; 4] = [128, 108, 56, 20];
+
+/// The smallest size in pixels of the player on the mini map.
+const MIN_PLAYER_SIZE: i32 = 6;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error<E> {
+    #[error(transparent)]
+    GeneralRenderingError(#[from] E),
+    #[error("An error at the end of drawing")]
+    EndDrawError,
+}
+
+struct RenderingData<R: Renderer> {
+    map_surface: R::Offscreen,
+    wall_surface: R::Bitmap,
+    mini_map_surface: R::Offscreen,
+
+    seed_font: R::Font,
+    message_font: R::Font,
+}
+
+/// Draws a [`GameState`] with any [`Renderer`].
+///
+/// Resources are created on the first draw and kept until the renderer
+/// reports the frame as lost.
+pub struct GameView<R: Renderer> {
+    /// The floor shown by the cached mini map, if drawn.
+    mini_map_floor: Option<u32>,
+    rendering_data: Option<RenderingData<R>>,
+}
+
+impl<R: Renderer> Default for GameView<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Renderer> GameView<R> {
+    pub fn new() -> Self {
+        GameView {
+            mini_map_floor: None,
+            rendering_data: None,
+        }
+    }
+
+    /// Makes the next draw repaint the cached mini map, e.g. after a new game started.
+    pub fn invalidate_mini_map(&mut self) {
+        self.mini_map_floor = None;
+    }
+
+    pub fn draw(&mut self, state: &GameState, rt: &mut R) -> Result<(), Error<R::Error>> {
+        let mut r = self
+            .rendering_data
+            .take()
+            .map_or_else(|| self.create_rendering_data(rt), Ok)?;
+
+        rt.begin();
+
+        rt.clear(WHITE);
+
+        rt.draw_text(
+            &format!(
+                "seed: {} ({})",
+                state.seed_text(),
+                state.settings().description()
+            ),
+            0,
+            0,
+            &r.seed_font,
+            BLACK,
+        );
+
+        rt.draw_rect(&rect_wh(48 - 1, 48 - 1, 256 + 2, 256 + 2), BLACK);
+        Self::draw_wall(&state.player().sight(state.map()), &mut r);
+        let map_bitmap = rt.get_bitmap(&r.map_surface)?;
+        rt.copy_from(&rect_wh(48, 48, 256, 256), &map_bitmap, 0, 0);
+
+        let mini_map_x = 48 * 2 + 256;
+        let mini_map_y = 48;
+        rt.draw_rect(
+            &rect_wh(mini_map_x - 1, mini_map_y - 1, 256 + 2, 256 + 2),
+            BLACK,
+        );
+        if state.shows_mini_map() {
+            let floor = state.player().floor;
+            if self.mini_map_floor != Some(floor) {
+                Self::draw_mini_map(state, &mut r);
+                self.mini_map_floor = Some(floor);
+            }
+            let mini_map_bitmap = rt.get_bitmap(&r.mini_map_surface)?;
+            rt.copy_from(
+                &rect_wh(mini_map_x, mini_map_y, 256, 256),
+                &mini_map_bitmap,
+                0,
+                0,
+            );
+
+            let map = state.map();
+            let player = state.player();
+            let tile_width = 256 / map.width as i32;
+            let tile_height = 256 / map.height as i32;
+            // A square, so that it keeps its shape when rotated, and still visible on large maps
+            let shape_size = tile_width.min(tile_height).max(MIN_PLAYER_SIZE);
+            let player_shape = [
+                point(0, shape_size / 2),
+                point(shape_size, 0),
+                point(shape_size, shape_size),
+            ];
+            let angle = match player.direction {
+                Direction::West => 0.0,
+                Direction::North => 90.0,
+                Direction::East => 180.0,
+                Direction::South => 270.0,
+            };
+            rt.draw_polygon(
+                &player_shape,
+                mini_map_x + (player.x * 256 / map.width) as i32 + (tile_width - shape_size) / 2,
+                mini_map_y + (player.y * 256 / map.height) as i32 + (tile_height - shape_size) / 2,
+                BLACK,
+                WHITE,
+                angle,
+            );
+
+            if map.floors > 1 {
+                let text = format!("floor {}/{}", floor + 1, map.floors);
+                rt.draw_text(&text, mini_map_x, mini_map_y - 16, &r.seed_font, BLACK);
+            }
+        }
+
+        if state.is_goal() {
+            let text = format!(
+                "ゴール！　スコア：{}点　{}手（最短{}手）",
+                state.score(),
+                state.key_press_count(),
+                state.par()
+            );
+            // Two lines fit in the bottom margin where one would not fit across
+            rt.draw_text(&text, 20, 48 + 256 + 2, &r.message_font, BLACK);
+            rt.draw_text(
+                "リスタート：Enterキー　終了：ESCキー",
+                20,
+                48 + 256 + 24,
+                &r.message_font,
+                BLACK,
+            );
+            rt.draw_text(&stats_text(state.stats()), 0, 16, &r.seed_font, BLACK);
+        } else {
+            rt.draw_text(
+                "移動：矢印キー マップ：Mキー 終了：ESCキー",
+                20,
+                48 + 256 + 12,
+                &r.message_font,
+                BLACK,
+            );
+        }
+
+        if rt.end() {
+            self.rendering_data = Some(r);
+        } else {
+            self.rendering_data = None;
+            return Err(Error::EndDrawError);
+        }
+
+        Ok(())
+    }
+
+    fn create_rendering_data(&mut self, rt: &mut R) -> Result<RenderingData<R>, R::Error> {
+        let map_surface = rt.create_offscreen(256, 256)?;
+        let wall_surface = rt.load_bitmap(WALL_BITMAP_PATH)?;
+        let mini_map_surface = rt.create_offscreen(256, 256)?;
+        let seed_font = rt.create_font("MS Gothic", 12)?;
+        let message_font = rt.create_font("MS UI Gothic", 20)?;
+
+        self.mini_map_floor = None;
+
+        Ok(RenderingData {
+            map_surface,
+            wall_surface,
+            mini_map_surface,
+            seed_font,
+            message_font,
+        })
+    }
+
+    fn draw_wall(sight: &Sight, r: &mut RenderingData<R>) {
+        let surface = &mut r.map_surface;
+        let wall = &r.wall_surface;
+
+        surface.begin();
+        surface.clear(BLACK);
+
+        surface.copy_from(&rect_wh(0, 0, 256, 256), wall, 0, 0);
+
+        for (forward, faces) in WALL_FACES.iter().enumerate().rev() {
+            for &(horizontal, (left, top, width, height), (src_x, src_y)) in *faces {
+                let face = sight.face(forward as u32, horizontal);
+                let dest_rect = rect_wh(left, top, width, height);
+                Self::copy_face(surface, wall, face, dest_rect, src_x, src_y);
+            }
+            if sight.is_under_bridge(forward as u32) {
+                if forward + 1 < PLANES.len() {
+                    Self::draw_bridge(surface, forward);
+                }
+                if forward > 0 {
+                    Self::draw_overpass(surface, forward);
+                }
+            }
+        }
+
+        surface.end();
+    }
+
+    /// Draws the underside of the bridge over the cell `forward` tiles
+    /// ahead, on the ceiling between the near and far faces of the cell.
+    fn draw_bridge(surface: &mut R::Offscreen, forward: usize) {
+        let (near, far) = (PLANES[forward], PLANES[forward + 1]);
+        let points = [
+            point(128 - near, 128 - near),
+            point(128 + near, 128 - near),
+            point(128 + far, 128 - far),
+            point(128 - far, 128 - far),
+        ];
+        surface.draw_polygon(&points, 0, 0, BLACK, BRIDGE_COLOR, 0.0);
+    }
+
+    /// Draws the front of the bridge crossing over the cell `forward` tiles
+    /// ahead, along the top of its near face.
+    fn draw_overpass(surface: &mut R::Offscreen, forward: usize) {
+        let half = PLANES[forward];
+        let band = rect(128 - half, 128 - half, 128 + half, 128 - half / 2);
+        surface.fill_rect(&band, BRIDGE_COLOR);
+        surface.draw_rect(&band, BLACK);
+    }
+
+    /// Copies a face of the wall bitmap to `dest_rect`, whole for walls and
+    /// below eye level only for railings.
+    fn copy_face(
+        surface: &mut R::Offscreen,
+        wall: &R::Bitmap,
+        face: Face,
+        dest_rect: Rect,
+        src_x: i32,
+        src_y: i32,
+    ) {
+        match face {
+            Face::Open => {}
+            Face::Wall => surface.copy_from(&dest_rect, wall, src_x, src_y),
+            Face::Railing => {
+                let top = dest_rect.top.max(128);
+                let lower = Rect { top, ..dest_rect };
+                surface.copy_from(&lower, wall, src_x, src_y + top - dest_rect.top);
+            }
+        }
+    }
+
+    fn draw_mini_map(state: &GameState, r: &mut RenderingData<R>) {
+        let surface = &mut r.mini_map_surface;
+
+        surface.begin();
+        surface.clear(WHITE);
+        let surface_size = surface.get_size();
+
+        let map = state.map();
+        let floor = state.player().floor;
+        let map_size = (map.width, map.height);
+        // Maps larger than the surface share pixels between tiles, but every tile covers one
+        let rect_at = |x: u32, y: u32| {
+            let left = x * surface_size.0 / map_size.0;
+            let top = y * surface_size.1 / map_size.1;
+            rect(
+                left as i32,
+                top as i32,
+                ((x + 1) * surface_size.0 / map_size.0).max(left + 1) as i32,
+                ((y + 1) * surface_size.1 / map_size.1).max(top + 1) as i32,
+            )
+        };
+        for y in 0..map.height {
+            for x in 0..map.width {
+                let is_edge = x == 0 || y == 0 || x == map.width - 1 || y == map.height - 1;
+                match map.get_on(floor, x as i32, y as i32) {
+                    Some(Tile::Floor) if is_edge => surface.fill_rect(&rect_at(x, y), SEAM_COLOR),
+                    Some(Tile::Floor) => {}
+                    Some(Tile::StairsUp) => surface.fill_rect(&rect_at(x, y), STAIRS_UP_COLOR),
+                    Some(Tile::StairsDown) => surface.fill_rect(&rect_at(x, y), STAIRS_DOWN_COLOR),
+                    Some(Tile::Crossing { .. }) => surface.fill_rect(&rect_at(x, y), BRIDGE_COLOR),
+                    _ => surface.fill_rect(&rect_at(x, y), BLACK),
+                }
+            }
+        }
+
+        if floor == 0 {
+            surface.fill_rect(&rect_at(map.start_x, map.start_y), START_COLOR);
+        }
+        if floor == map.goal_floor {
+            surface.fill_rect(&rect_at(map.goal_x, map.goal_y), GOAL_COLOR);
+        }
+
+        surface.end();
+    }
+}
+
+/// The stats as shown to players once the maze is solved, in the window and
+/// in the terminal alike.
+pub fn stats_text(stats: &MazeStats) -> String {
+    let solution = match stats.solution_length {
+        Some(length) => format!("最短{length}歩"),
+        None => String::from("ゴールに着けない迷路"),
+    };
+    format!(
+        "{solution}　行き止まり{}か所　分かれ道{}か所　通路の長さ平均{:.1}歩　分岐{}回　到達できる範囲{:.0}％",
+        stats.dead_ends,
+        stats.junctions,
+        stats.average_corridor_length,
+        stats.decision_points,
+        stats.reachable_ratio * 100.0
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Settings;
+    use crate::map::{Axis, Map, Size};
+    use crate::player::{crossroads, Player};
+    use crate::render::{Command, Recorder};
+
+    fn draw(view: &mut GameView<Recorder>, state: &GameState, rt: &mut Recorder) -> Vec<Command> {
+        rt.clear_commands();
+        view.draw(state, rt).unwrap();
+        rt.commands().into_iter().map(|(_, c)| c).collect()
+    }
+
+    /// The commands drawing the first-person view of `player` on `map`.
+    fn draw_wall(map: &Map, player: Player) -> Vec<Command> {
+        let mut view = GameView::new();
+        let mut rt = Recorder::new(WIDTH, HEIGHT);
+        let mut r = view.create_rendering_data(&mut rt).unwrap();
+        rt.clear_commands();
+        GameView::<Recorder>::draw_wall(&player.sight(map), &mut r);
+        rt.commands().into_iter().map(|(_, c)| c).collect()
+    }
+
+    fn copy(dest_rect: Rect, src_x: i32, src_y: i32) -> Command {
+        Command::CopyFrom {
+            dest_rect,
+            src: String::from(WALL_BITMAP_PATH),
+            src_x,
+            src_y,
+        }
+    }
+
+    #[test]
+    fn draws_railings_along_bridges() {
+        let map = crossroads();
+        let on_bridge = Player {
+            x: 3,
+            y: 3,
+            floor: 0,
+            direction: Direction::East,
+            crossing: Some(Axis::Horizontal),
+        };
+        let commands = draw_wall(&map, on_bridge);
+        assert!(commands.contains(&copy(rect(0, 128, 20, 256), 256, 128)));
+        assert!(commands.contains(&copy(rect(236, 128, 256, 256), 492, 128)));
+        assert!(!commands.contains(&copy(rect_wh(0, 0, 20, 256), 256, 0)));
+        assert!(!commands.contains(&Command::FillRect(rect(20, 20, 236, 74), BRIDGE_COLOR)));
+
+        // Walking up to the bridge
+        let commands = draw_wall(
+            &map,
+            Player {
+                x: 1,
+                crossing: None,
+                ..on_bridge
+            },
+        );
+        assert!(commands.contains(&copy(rect(21, 128, 110, 184), 788, 128)));
+        assert!(commands.contains(&copy(rect(147, 128, 236, 184), 915, 128)));
+    }
+
+    #[test]
+    fn draws_overpasses_across_the_corridor() {
+        let map = crossroads();
+        let under = Player {
+            x: 3,
+            y: 1,
+            floor: 0,
+            direction: Direction::South,
+            crossing: None,
+        };
+        let commands = draw_wall(&map, under);
+        assert!(commands.contains(&Command::FillRect(rect(72, 72, 184, 100), BRIDGE_COLOR)));
+        // The passages of the bridge are out of reach from below
+        assert!(commands.contains(&copy(rect_wh(21, 72, 89, 112), 788, 72)));
+        assert!(commands.contains(&copy(rect_wh(147, 72, 89, 112), 915, 72)));
+
+        // Right under it, the overpass is above the view
+        let commands = draw_wall(
+            &map,
+            Player {
+                y: 3,
+                crossing: Some(Axis::Vertical),
+                ..under
+            },
+        );
+        assert!(!commands
+            .iter()
+            .any(|c| matches!(c, Command::FillRect(_, BRIDGE_COLOR))));
+        assert!(commands.contains(&copy(rect_wh(0, 0, 20, 256), 256, 0)));
+        assert!(commands.contains(&copy(rect_wh(236, 0, 20, 256), 492, 0)));
+    }
+
+    #[test]
+    fn hides_mini_map_until_toggled() {
+        let mut state = GameState::new([3; 32]);
+        let mut view = GameView::new();
+        let mut rt = Recorder::new(WIDTH, HEIGHT);
+
+        let is_polygon = |c: &Command| matches!(c, Command::DrawPolygon { .. });
+        assert!(!draw(&mut view, &state, &mut rt).iter().any(is_polygon));
+
+        state.toggle_mini_map();
+        let commands = draw(&mut view, &state, &mut rt);
+        let polygon = commands.iter().find(|c| is_polygon(c)).unwrap();
+        let Command::DrawPolygon { x, y, angle, .. } = polygon else {
+            unreachable!();
+        };
+        assert_eq!((*x, *y), (352 + 12, 48 + 12));
+        assert_eq!(*angle, 270.0);
+    }
+
+    #[test]
+    fn mini_map_is_drawn_once() {
+        let mut state = GameState::new([3; 32]);
+        state.toggle_mini_map();
+        let mut view = GameView::new();
+        let mut rt = Recorder::new(WIDTH, HEIGHT);
+
+        let is_mini_map_fill =
+            |c: &(String, Command)| c.0 == "offscreen1" && matches!(c.1, Command::FillRect(..));
+        view.draw(&state, &mut rt).unwrap();
+        assert!(rt.commands().iter().any(is_mini_map_fill));
+
+        rt.clear_commands();
+        view.draw(&state, &mut rt).unwrap();
+        assert!(!rt.commands().iter().any(is_mini_map_fill));
+
+        view.invalidate_mini_map();
+        view.draw(&state, &mut rt).unwrap();
+        assert!(rt.commands().iter().any(is_mini_map_fill));
+    }
+
+    #[test]
+    fn shows_seed() {
+        let state = GameState::new([0; 32]);
+        let mut view = GameView::new();
+        let mut rt = Recorder::new(WIDTH, HEIGHT);
+        let commands = draw(&mut view, &state, &mut rt);
+        assert!(commands.contains(&Command::DrawText {
+            text: format!("seed: 0x{} (bar-knocking)", "00".repeat(32)),
+            x: 0,
+            y: 0,
+            font: String::from("MS Gothic:12"),
+            color: BLACK,
+        }));
+    }
+
+    #[test]
+    fn scales_player_to_map_size() {
+        let settings = Settings {
+            size: Size::new(301, 101).unwrap(),
+            ..Default::default()
+        };
+        let mut state = GameState::with_settings([3; 32], settings);
+        state.toggle_mini_map();
+        let mut view = GameView::new();
+        let mut rt = Recorder::new(WIDTH, HEIGHT);
+
+        let commands = draw(&mut view, &state, &mut rt);
+        let Some(Command::DrawPolygon { points, x, y, .. }) = commands
+            .iter()
+            .find(|c| matches!(c, Command::DrawPolygon { .. }))
+        else {
+            panic!("no player drawn");
+        };
+        assert_eq!(points[2], point(MIN_PLAYER_SIZE, MIN_PLAYER_SIZE));
+        // Centered on the tile at (1, 1), which is 0 by 2 pixels
+        assert_eq!((*x, *y), (352 - 3, 48 + 2 - 2));
+    }
+
+    #[test]
+    fn words_the_stats_for_players() {
+        let stats = MazeStats {
+            solution_length: Some(40),
+            dead_ends: 12,
+            junctions: 9,
+            average_corridor_length: 3.25,
+            decision_points: 4,
+            reachable_ratio: 1.0,
+        };
+        assert_eq!(
+            stats_text(&stats),
+            "最短40歩　行き止まり12か所　分かれ道9か所　通路の長さ平均3.2歩　分岐4回　到達できる範囲100％"
+        );
+        let stats = MazeStats {
+            solution_length: None,
+            ..stats
+        };
+        assert!(stats_text(&stats).starts_with("ゴールに着けない迷路　"));
+    }
+}
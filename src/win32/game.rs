@@ -0,0 +1,36 @@
+use super::render::RenderTarget;
+use maze3d::game::{GameState, Settings};
+use maze3d::view::GameView;
+
+pub type Error = maze3d::view::Error<super::render::Error>;
+
+pub struct Game {
+    state: GameState,
+    view: GameView<RenderTarget>,
+}
+
+impl Game {
+    pub fn new(settings: Settings) -> Self {
+        Game {
+            state: GameState::first_game(settings),
+            view: GameView::new(),
+        }
+    }
+
+    pub fn state_mut(&mut self) -> &mut GameState {
+        &mut self.state
+    }
+
+    pub fn new_game(&mut self) -> Game {
+        let mut view = std::mem::take(&mut self.view);
+        view.invalidate_mini_map();
+        Game {
+            state: self.state.next_game(),
+            view,
+        }
+    }
+
+    pub fn draw(&mut self, rt: &mut RenderTarget) -> Result<(), Error> {
+        self.view.draw(&self.state, rt)
+    }
+}
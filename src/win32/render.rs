@@ -0,0 +1,500 @@
+use maze3d::render::{rect, rect_wh, Canvas, Color, Point, Rect, Renderer};
+use std::collections::HashMap;
+use windows::{
+    core::{w, HSTRING},
+    Foundation::Numerics::Matrix3x2,
+    Win32::{
+        Foundation::{GENERIC_READ, HWND, RECT},
+        Graphics::{
+            Direct2D::{
+                Common::{
+                    D2D1_COLOR_F, D2D1_FIGURE_BEGIN_FILLED, D2D1_FIGURE_END_CLOSED, D2D_POINT_2F,
+                    D2D_RECT_F, D2D_SIZE_F, D2D_SIZE_U,
+                },
+                D2D1CreateFactory, ID2D1Bitmap, ID2D1BitmapRenderTarget, ID2D1Brush, ID2D1Factory,
+                ID2D1GeometrySink, ID2D1HwndRenderTarget, ID2D1PathGeometry, ID2D1RenderTarget,
+                ID2D1SolidColorBrush, D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
+                D2D1_COMPATIBLE_RENDER_TARGET_OPTIONS_NONE, D2D1_DRAW_TEXT_OPTIONS_NONE,
+                D2D1_FACTORY_TYPE_SINGLE_THREADED, D2D1_HWND_RENDER_TARGET_PROPERTIES,
+                D2D1_PRESENT_OPTIONS_NONE, D2D1_RENDER_TARGET_PROPERTIES,
+            },
+            DirectWrite::{
+                DWriteCreateFactory, IDWriteFactory, IDWriteTextFormat, DWRITE_FACTORY_TYPE_SHARED,
+                DWRITE_FONT_STRETCH_NORMAL, DWRITE_FONT_STYLE_NORMAL, DWRITE_FONT_WEIGHT_REGULAR,
+                DWRITE_MEASURING_MODE_NATURAL, DWRITE_PARAGRAPH_ALIGNMENT_NEAR,
+                DWRITE_TEXT_ALIGNMENT_LEADING,
+            },
+            Imaging::{
+                CLSID_WICImagingFactory, GUID_WICPixelFormat32bppPBGRA, IWICImagingFactory,
+                WICBitmapDitherTypeNone, WICBitmapPaletteTypeMedianCut,
+                WICDecodeMetadataCacheOnLoad,
+            },
+        },
+        System::Com::{CoCreateInstance, CoInitialize, CLSCTX_INPROC_SERVER},
+        UI::WindowsAndMessaging::GetClientRect,
+    },
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Internal(windows::core::Error),
+    #[error("Cannot load a bitmap from file ({file_path}): {source}")]
+    BitmapLoad {
+        file_path: String,
+        #[source]
+        source: windows::core::Error,
+    },
+    #[error("Cannot create a font named \"{face_name}\"")]
+    FontCreation {
+        face_name: String,
+        #[source]
+        source: windows::core::Error,
+    },
+}
+
+pub struct Context {
+    d2d_factory: ID2D1Factory,
+    primary_render_target: Option<RenderTarget>,
+
+    dwrite_factory: IDWriteFactory,
+    image_loader: ImageLoader,
+}
+
+impl Context {
+    pub fn new() -> Result<Self, Error> {
+        let d2d_factory = unsafe { D2D1CreateFactory(D2D1_FACTORY_TYPE_SINGLE_THREADED, None) }
+            .map_err(Error::Internal)?;
+
+        let dwrite_factory =
+            unsafe { DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED) }.map_err(Error::Internal)?;
+
+        Ok(Context {
+            d2d_factory,
+            primary_render_target: None,
+
+            dwrite_factory,
+            image_loader: ImageLoader::new()?,
+        })
+    }
+
+    pub fn get_primary_render_target(&mut self, hwnd: HWND) -> Result<&mut RenderTarget, Error> {
+        if self.primary_render_target.is_some() {
+            return Ok(self.primary_render_target.as_mut().unwrap());
+        }
+
+        let mut rect = RECT::default();
+        _ = unsafe { GetClientRect(hwnd, &mut rect) };
+
+        let size = D2D_SIZE_U {
+            width: (rect.right - rect.left) as u32,
+            height: (rect.bottom - rect.top) as u32,
+        };
+
+        let hwnd_render_target = unsafe {
+            self.d2d_factory.CreateHwndRenderTarget(
+                &D2D1_RENDER_TARGET_PROPERTIES::default(),
+                &D2D1_HWND_RENDER_TARGET_PROPERTIES {
+                    hwnd,
+                    pixelSize: size,
+                    presentOptions: D2D1_PRESENT_OPTIONS_NONE,
+                },
+            )
+        }
+        .map_err(Error::Internal)?;
+
+        let render_target = RenderTarget {
+            target: Target::WindowRenderTarget(hwnd_render_target),
+            dwrite_factory: self.dwrite_factory.clone(),
+            image_loader: self.image_loader.clone(),
+            brushes: HashMap::new(),
+            geometries: HashMap::new(),
+        };
+
+        self.primary_render_target = Some(render_target);
+
+        Ok(self.primary_render_target.as_mut().unwrap())
+    }
+
+    pub fn reset_primary_render_target(&mut self) {
+        self.primary_render_target = None;
+    }
+}
+
+fn create_geometry<F: FnOnce(&GeometryBuilder)>(
+    factory: &ID2D1Factory,
+    creator: F,
+) -> Result<Geometry, Error> {
+    let geometry = unsafe { factory.CreatePathGeometry() }.map_err(Error::Internal)?;
+
+    let sink = unsafe { geometry.Open() }.map_err(Error::Internal)?;
+    creator(&GeometryBuilder(&sink));
+    unsafe { sink.Close() }.map_err(Error::Internal)?;
+
+    Ok(Geometry(geometry))
+}
+
+pub struct RenderTarget {
+    target: Target,
+    dwrite_factory: IDWriteFactory,
+    image_loader: ImageLoader,
+    /// The brushes of the target, one per color, made on first use and
+    /// dropped with the target when the device is lost
+    brushes: HashMap<Color, Brush>,
+    /// The polygons of the target with their bounds, one per point set,
+    /// made on first use like the brushes
+    geometries: HashMap<Vec<Point>, (Geometry, D2D_RECT_F)>,
+}
+
+enum Target {
+    WindowRenderTarget(ID2D1HwndRenderTarget),
+    BitmapRenderTarget(ID2D1BitmapRenderTarget),
+}
+
+impl RenderTarget {
+    fn get_common(&self) -> &ID2D1RenderTarget {
+        match &self.target {
+            Target::WindowRenderTarget(rt) => rt.into(),
+            Target::BitmapRenderTarget(rt) => rt.into(),
+        }
+    }
+
+    fn get_bitmap(&self) -> Result<Bitmap, Error> {
+        match &self.target {
+            Target::BitmapRenderTarget(rt) => {
+                let bitmap = unsafe { rt.GetBitmap() }.map_err(Error::Internal)?;
+                Ok(Bitmap(bitmap))
+            }
+            _ => panic!("Cannot get bitmap from HWND render target"),
+        }
+    }
+
+    fn solid_brush(&mut self, color: Color) -> Result<Brush, Error> {
+        if let Some(brush) = self.brushes.get(&color) {
+            return Ok(brush.clone());
+        }
+        let brush = unsafe {
+            self.get_common()
+                .CreateSolidColorBrush(&to_color_f(color), None)
+        }
+        .map_err(Error::Internal)?;
+        let brush = Brush::SolidColor(brush);
+        self.brushes.insert(color, brush.clone());
+        Ok(brush)
+    }
+
+    fn polygon(&mut self, points: &[Point]) -> Result<(Geometry, D2D_RECT_F), Error> {
+        if let Some(polygon) = self.geometries.get(points) {
+            return Ok(polygon.clone());
+        }
+        let factory = unsafe { self.get_common().GetFactory() }.map_err(Error::Internal)?;
+        let geometry = create_geometry(&factory, |p| {
+            let Some((first, rest)) = points.split_first() else {
+                return;
+            };
+            p.begin_figure(&to_point_f(first));
+            for point in rest {
+                p.add_line(&to_point_f(point));
+            }
+            p.end_figure();
+        })?;
+        let bounds = unsafe { geometry.0.GetBounds(None) }.map_err(Error::Internal)?;
+        self.geometries
+            .insert(points.to_vec(), (geometry.clone(), bounds));
+        Ok((geometry, bounds))
+    }
+}
+
+impl Canvas for RenderTarget {
+    type Bitmap = Bitmap;
+    type Font = Font;
+
+    fn begin(&mut self) {
+        unsafe { self.get_common().BeginDraw() };
+    }
+
+    fn end(&mut self) -> bool {
+        unsafe { self.get_common().EndDraw(None, None) }.is_ok()
+    }
+
+    fn get_size(&self) -> (u32, u32) {
+        let size = unsafe { self.get_common().GetSize() };
+        (size.width as u32, size.height as u32)
+    }
+
+    fn clear(&mut self, color: Color) {
+        unsafe {
+            self.get_common().SetTransform(&Matrix3x2::identity());
+            self.get_common().Clear(Some(&to_color_f(color)));
+        }
+    }
+
+    fn copy_from(&mut self, dest_rect: &Rect, src: &Bitmap, src_x: i32, src_y: i32) {
+        unsafe {
+            self.get_common().DrawBitmap(
+                &src.0,
+                Some(&to_rect_f(dest_rect)),
+                1.0,
+                D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
+                Some(&to_rect_f(&rect_wh(
+                    src_x,
+                    src_y,
+                    dest_rect.width(),
+                    dest_rect.height(),
+                ))),
+            )
+        }
+    }
+
+    fn fill_rect(&mut self, rect: &Rect, color: Color) {
+        // Failures surface as a lost frame at EndDraw
+        let Ok(brush) = self.solid_brush(color) else {
+            return;
+        };
+        unsafe {
+            self.get_common()
+                .FillRectangle(&to_rect_f(rect), brush.get_common())
+        };
+    }
+
+    fn draw_rect(&mut self, rect: &Rect, color: Color) {
+        let Ok(brush) = self.solid_brush(color) else {
+            return;
+        };
+        unsafe {
+            self.get_common()
+                .DrawRectangle(&to_rect_f(rect), brush.get_common(), 1.0, None)
+        };
+    }
+
+    fn draw_polygon(
+        &mut self,
+        points: &[Point],
+        x: i32,
+        y: i32,
+        pen: Color,
+        fill: Color,
+        angle: f32,
+    ) {
+        let (Ok(pen), Ok(brush)) = (self.solid_brush(pen), self.solid_brush(fill)) else {
+            return;
+        };
+        let Ok((geometry, bounds)) = self.polygon(points) else {
+            return;
+        };
+        let rt = self.get_common();
+        unsafe {
+            rt.SetTransform(
+                &(Matrix3x2::rotation(
+                    angle,
+                    (bounds.right - bounds.left) / 2.0,
+                    (bounds.bottom - bounds.top) / 2.0,
+                ) * Matrix3x2::translation(x as f32, y as f32)),
+            );
+            rt.DrawGeometry(&geometry.0, pen.get_common(), 1.0, None);
+            rt.FillGeometry(&geometry.0, brush.get_common(), None);
+            rt.SetTransform(&Matrix3x2::identity());
+        }
+    }
+
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, font: &Font, color: Color) {
+        let Ok(brush) = self.solid_brush(color) else {
+            return;
+        };
+        unsafe {
+            self.get_common().DrawText(
+                HSTRING::from(text).as_wide(),
+                &font.0,
+                &to_rect_f(&rect(x, y, 10000, 10000)),
+                brush.get_common(),
+                D2D1_DRAW_TEXT_OPTIONS_NONE,
+                DWRITE_MEASURING_MODE_NATURAL,
+            )
+        }
+    }
+}
+
+impl Renderer for RenderTarget {
+    type Error = Error;
+    type Offscreen = RenderTarget;
+
+    fn create_offscreen(&mut self, width: u32, height: u32) -> Result<RenderTarget, Error> {
+        let size = D2D_SIZE_F {
+            width: width as f32,
+            height: height as f32,
+        };
+        let new_rt = unsafe {
+            self.get_common().CreateCompatibleRenderTarget(
+                Some(&size),
+                None,
+                None,
+                D2D1_COMPATIBLE_RENDER_TARGET_OPTIONS_NONE,
+            )
+        }
+        .map_err(Error::Internal)?;
+
+        Ok(RenderTarget {
+            target: Target::BitmapRenderTarget(new_rt),
+            dwrite_factory: self.dwrite_factory.clone(),
+            image_loader: self.image_loader.clone(),
+            brushes: HashMap::new(),
+            geometries: HashMap::new(),
+        })
+    }
+
+    fn get_bitmap(&mut self, offscreen: &RenderTarget) -> Result<Bitmap, Error> {
+        offscreen.get_bitmap()
+    }
+
+    fn load_bitmap(&mut self, file_path: &str) -> Result<Bitmap, Error> {
+        self.image_loader.load_bitmap(file_path, self)
+    }
+
+    fn create_font(&mut self, face_name: &str, size: i32) -> Result<Font, Error> {
+        let text_format = unsafe {
+            self.dwrite_factory
+                .CreateTextFormat(
+                    &HSTRING::from(face_name),
+                    None,
+                    DWRITE_FONT_WEIGHT_REGULAR,
+                    DWRITE_FONT_STYLE_NORMAL,
+                    DWRITE_FONT_STRETCH_NORMAL,
+                    size as f32,
+                    w!("ja-jp"),
+                )
+                .and_then(|tf| {
+                    tf.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_LEADING)?;
+                    tf.SetParagraphAlignment(DWRITE_PARAGRAPH_ALIGNMENT_NEAR)?;
+                    Ok(tf)
+                })
+        }
+        .map_err(|e| Error::FontCreation {
+            face_name: face_name.to_owned(),
+            source: e,
+        })?;
+
+        Ok(Font(text_format))
+    }
+}
+
+pub struct Bitmap(ID2D1Bitmap);
+
+#[derive(Clone)]
+pub struct ImageLoader {
+    wic_factory: IWICImagingFactory,
+}
+
+impl ImageLoader {
+    pub fn new() -> Result<Self, Error> {
+        let result = unsafe { CoInitialize(None) };
+        if result.is_err() {
+            return Err(Error::Internal(windows::core::Error::from_hresult(result)));
+        }
+        let wic_factory: IWICImagingFactory =
+            unsafe { CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER) }
+                .map_err(Error::Internal)?;
+
+        Ok(ImageLoader { wic_factory })
+    }
+
+    pub fn load_bitmap(&self, file_path: &str, rt: &RenderTarget) -> Result<Bitmap, Error> {
+        let convert_error = |e: windows::core::Error| Error::BitmapLoad {
+            file_path: file_path.to_owned(),
+            source: e,
+        };
+
+        let decoder = unsafe {
+            self.wic_factory.CreateDecoderFromFilename(
+                &HSTRING::from(file_path),
+                None,
+                GENERIC_READ,
+                WICDecodeMetadataCacheOnLoad,
+            )
+        }
+        .map_err(convert_error)?;
+
+        let frame = unsafe { decoder.GetFrame(0) }.map_err(convert_error)?;
+
+        let format_converter =
+            unsafe { self.wic_factory.CreateFormatConverter() }.map_err(convert_error)?;
+
+        unsafe {
+            format_converter.Initialize(
+                &frame,
+                &GUID_WICPixelFormat32bppPBGRA,
+                WICBitmapDitherTypeNone,
+                None,
+                0.0,
+                WICBitmapPaletteTypeMedianCut,
+            )
+        }
+        .map_err(convert_error)?;
+
+        let bitmap = unsafe {
+            rt.get_common()
+                .CreateBitmapFromWicBitmap(&format_converter, None)
+        }
+        .map_err(convert_error)?;
+
+        Ok(Bitmap(bitmap))
+    }
+}
+
+#[derive(Clone)]
+pub enum Brush {
+    SolidColor(ID2D1SolidColorBrush),
+}
+
+impl Brush {
+    fn get_common(&self) -> &ID2D1Brush {
+        match self {
+            Self::SolidColor(brush) => brush.into(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Geometry(ID2D1PathGeometry);
+
+pub struct GeometryBuilder<'a>(&'a ID2D1GeometrySink);
+
+impl<'a> GeometryBuilder<'a> {
+    pub fn begin_figure(&self, point: &D2D_POINT_2F) {
+        unsafe { self.0.BeginFigure(*point, D2D1_FIGURE_BEGIN_FILLED) };
+    }
+
+    pub fn end_figure(&self) {
+        unsafe { self.0.EndFigure(D2D1_FIGURE_END_CLOSED) };
+    }
+
+    pub fn add_line(&self, point: &D2D_POINT_2F) {
+        unsafe { self.0.AddLine(*point) };
+    }
+}
+
+pub struct Font(IDWriteTextFormat);
+
+fn to_point_f(point: &Point) -> D2D_POINT_2F {
+    D2D_POINT_2F {
+        x: point.x as f32,
+        y: point.y as f32,
+    }
+}
+
+fn to_rect_f(rect: &Rect) -> D2D_RECT_F {
+    D2D_RECT_F {
+        left: rect.left as f32,
+        top: rect.top as f32,
+        right: rect.right as f32,
+        bottom: rect.bottom as f32,
+    }
+}
+
+fn to_color_f(color: Color) -> D2D1_COLOR_F {
+    D2D1_COLOR_F {
+        r: color.r as f32 / 255.0,
+        g: color.g as f32 / 255.0,
+        b: color.b as f32 / 255.0,
+        a: 1.0,
+    }
+}